@@ -0,0 +1,146 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{
+    frame_stats::ms,
+    results::{RunResult, SETTINGS},
+};
+
+/// A previously exported result to compare runs against.
+#[derive(Resource, Clone, Debug)]
+pub struct Baseline {
+    /// JSON text of the [`SETTINGS`] the baseline has, which runs must match.
+    pub settings: Vec<(String, String)>,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub max_count: Option<f64>,
+    /// Allowed relative change before a metric counts as a regression.
+    pub tolerance: f64,
+}
+
+impl Baseline {
+    /// Loads a result written by [`RunResult::write`], from its `.json` file.
+    pub fn load(path: &Path, tolerance: f64) -> Result<Self, String> {
+        let path = path.with_extension("json");
+        let json = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        Self::parse(&json, tolerance).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Parses the flat JSON object [`RunResult::to_json`] produces, failing if
+    /// it has none of the metrics to compare.
+    pub fn parse(json: &str, tolerance: f64) -> Result<Self, String> {
+        let raw_field = |name: &str| {
+            json.lines().find_map(|line| {
+                let (key, value) = line.trim().trim_end_matches(',').split_once(':')?;
+                (key.trim().trim_matches('"') == name).then(|| value.trim())
+            })
+        };
+        let field = |name: &str| raw_field(name)?.parse().ok();
+
+        let baseline = Self {
+            settings: SETTINGS
+                .iter()
+                .filter_map(|&name| Some((name.to_string(), raw_field(name)?.to_string())))
+                .collect(),
+            p50_ms: field("p50_ms"),
+            p95_ms: field("p95_ms"),
+            max_count: field("max_count"),
+            tolerance,
+        };
+        if baseline.p50_ms.is_none() && baseline.p95_ms.is_none() && baseline.max_count.is_none() {
+            return Err("no p50_ms, p95_ms or max_count to compare against".into());
+        }
+        Ok(baseline)
+    }
+
+    /// Prints how `result` compares to the baseline and returns whether it
+    /// fails: a metric regressed beyond the tolerance, or the run measured
+    /// something else, such as a different mode, scenario, count or render
+    /// path.
+    pub fn check(&self, result: &RunResult) -> bool {
+        // Searches end at whatever count they last tried, so their count
+        // doesn't have to match, and their frame times mix the last steps'
+        // counts.
+        let searched = result.mode == "search";
+        let mut comparable = true;
+        for (name, current) in result.settings() {
+            if searched && name == "count" {
+                continue;
+            }
+            let Some((_, baseline)) = self.settings.iter().find(|(key, _)| *key == name) else {
+                continue;
+            };
+            if *baseline != current {
+                eprintln!("{name} {current} differs from the baseline's {baseline}");
+                comparable = false;
+            }
+        }
+        if !comparable {
+            eprintln!("The run is not comparable to the baseline");
+            return true;
+        }
+
+        let frame_times = (!searched).then_some(&result.frame_times);
+        let metrics = [
+            ("p50_ms", self.p50_ms, frame_times.map(|f| ms(f.p50)), false),
+            ("p95_ms", self.p95_ms, frame_times.map(|f| ms(f.p95)), false),
+            (
+                "max_count",
+                self.max_count,
+                result.max_count.map(f64::from),
+                true,
+            ),
+        ];
+
+        let mut regressed = false;
+        for (name, baseline, current, higher_is_better) in metrics {
+            let (Some(baseline), Some(current)) = (baseline, current) else {
+                continue;
+            };
+            let change = if baseline == 0. {
+                0.
+            } else {
+                (current - baseline) / baseline
+            };
+            let worse = if higher_is_better {
+                -change > self.tolerance
+            } else {
+                change > self.tolerance
+            };
+            regressed |= worse;
+            println!(
+                "{name}: {current:.2} vs baseline {baseline:.2} ({:+.1}%){}",
+                change * 100.,
+                if worse { " REGRESSION" } else { "" }
+            );
+        }
+        regressed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_results() {
+        let json = "{\n  \"mode\": \"bench\",\n  \"count\": 8000,\n  \"p50_ms\": 4.5,\n  \"max_count\": null\n}\n";
+        let baseline = Baseline::parse(json, 0.05).unwrap();
+        assert_eq!(
+            baseline.settings,
+            [
+                ("mode".to_string(), "\"bench\"".to_string()),
+                ("count".to_string(), "8000".to_string()),
+            ]
+        );
+        assert_eq!(baseline.p50_ms, Some(4.5));
+        assert_eq!(baseline.max_count, None);
+    }
+
+    #[test]
+    fn rejects_results_without_metrics() {
+        assert!(Baseline::parse("{\"foo\": 1}", 0.05).is_err());
+    }
+}
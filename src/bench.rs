@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    frame_stats::{FrameTimeSummary, RecordFrame},
+    results::{report, RunContext},
+    scenario::Stats,
+};
+
+/// Records every frame time at the current count for a fixed duration, then
+/// prints a report and exits.
+pub struct BenchPlugin {
+    pub warmup: Duration,
+    pub duration: Duration,
+}
+
+impl Plugin for BenchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bench {
+            warmup: self.warmup,
+            duration: self.duration,
+            elapsed: Duration::ZERO,
+            frame_times: Vec::new(),
+        });
+        app.add_systems(Last, record.after(RecordFrame));
+    }
+}
+
+#[derive(Resource)]
+struct Bench {
+    warmup: Duration,
+    duration: Duration,
+    elapsed: Duration,
+    frame_times: Vec<Duration>,
+}
+
+fn record(time: Res<Time<Real>>, mut bench: ResMut<Bench>, stats: Res<Stats>, mut run: RunContext) {
+    bench.elapsed += time.delta();
+    if bench.elapsed <= bench.warmup {
+        return;
+    }
+    bench.frame_times.push(time.delta());
+    if bench.elapsed < bench.warmup + bench.duration {
+        return;
+    }
+
+    let result = run.result(
+        "bench",
+        stats.count,
+        FrameTimeSummary::new(bench.frame_times.drain(..)),
+    );
+    report(format!("Count: {}\n{}", result.count, result.frame_times).trim_end());
+    run.finish(&result);
+}
@@ -0,0 +1,39 @@
+use bevy::{
+    asset::load_internal_asset,
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{Material2d, Material2dPlugin},
+};
+
+const SHADER: Handle<Shader> = Handle::weak_from_u128(0x2b4f_9c1e_77d3_4a60_8e15_c0a9_5f3b_d842);
+
+/// Renders [`BorderedRectMaterial`]s.
+pub struct BorderedRectPlugin;
+
+impl Plugin for BorderedRectPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, SHADER, "bordered_rect.wgsl", Shader::from_wgsl);
+        app.add_plugins(Material2dPlugin::<BorderedRectMaterial>::default());
+    }
+}
+
+/// Draws a quad's fill and border in one pass, so a bordered rectangle needs
+/// a single entity instead of a border sprite with a fill sprite on top.
+///
+/// The border width is in screen pixels, so quads of any scale can share one
+/// material.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct BorderedRectMaterial {
+    #[uniform(0)]
+    pub border_color: LinearRgba,
+    #[uniform(0)]
+    pub fill_color: LinearRgba,
+    #[uniform(0)]
+    pub border_width: f32,
+}
+
+impl Material2d for BorderedRectMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER.into()
+    }
+}
@@ -0,0 +1,17 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct BorderedRectMaterial {
+    border_color: vec4<f32>,
+    fill_color: vec4<f32>,
+    border_width: f32,
+}
+
+@group(2) @binding(0) var<uniform> material: BorderedRectMaterial;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // Distance to the nearest edge in screen pixels, whatever the quad's scale.
+    let to_edge = min(mesh.uv, 1.0 - mesh.uv) / fwidth(mesh.uv);
+    let in_fill = min(to_edge.x, to_edge.y) >= material.border_width;
+    return select(material.border_color, material.fill_color, in_fill);
+}
@@ -0,0 +1,182 @@
+use bevy::{
+    image::ImageSampler,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use rand::Rng;
+
+use crate::{
+    scenario::{self, PseudoRng, Scenario, ScenarioObject, ScenarioSet, Timestep},
+    viewport::Viewport,
+};
+
+/// Acceleration in pixels per second squared, the classic 0.75 px/frame² at 60 FPS.
+const GRAVITY: f32 = 2700.;
+/// Share of the vertical speed kept when bouncing off the floor.
+const BOUNCE: f32 = 0.85;
+const BUNNY_SIZE: Vec2 = Vec2::new(24., 32.);
+
+/// Pixel art for the bunny texture: `#` outline, `w` fur, `p` pink, `.` clear.
+const BUNNY: [&str; 16] = [
+    "..##....##..",
+    ".#pp#..#pp#.",
+    ".#pp#..#pp#.",
+    ".#pp#..#pp#.",
+    ".#ww#..#ww#.",
+    "..#w####w#..",
+    ".#wwwwwwww#.",
+    "#wwwwwwwwww#",
+    "#ww#wwww#ww#",
+    "#wwwwwwwwww#",
+    "#wwwwppwwww#",
+    ".#wwwwwwww#.",
+    ".#wwwwwwww#.",
+    "#wwwwwwwwww#",
+    "#wwwwwwwwww#",
+    ".##########.",
+];
+
+/// Textured sprites falling under gravity and bouncing off the viewport edges,
+/// as in the PixiJS bunnymark.
+pub struct Bunnymark;
+
+impl Scenario for Bunnymark {
+    fn name(&self) -> &'static str {
+        "bunnymark"
+    }
+
+    fn description(&self) -> &'static str {
+        "Textured sprites falling under gravity and bouncing off the edges"
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            movement
+                .in_set(ScenarioSet::Simulate)
+                .ambiguous_with(ScenarioSet::Simulate)
+                .run_if(scenario::active(self.name())),
+        );
+    }
+
+    fn setup(&self, world: &mut World) {
+        if world.contains_resource::<BunnyTexture>() {
+            return;
+        }
+        // Headless apps have no image assets, and their sprites are never drawn.
+        let texture = world
+            .get_resource_mut::<Assets<Image>>()
+            .map(|mut images| images.add(bunny_image()))
+            .unwrap_or_default();
+        world.insert_resource(BunnyTexture(texture));
+    }
+
+    fn spawn(&self, world: &mut World, num: u32) {
+        world
+            .run_system_cached_with(spawn_bunnies, num)
+            .expect("spawning bunnies failed");
+    }
+}
+
+#[derive(Resource)]
+struct BunnyTexture(Handle<Image>);
+
+#[derive(Component)]
+#[require(ScenarioObject)]
+struct Bunny {
+    velocity: Vec2,
+    /// Minimum upward speed after bouncing off the floor, so bunnies never
+    /// come to rest.
+    jump: f32,
+}
+
+fn bunny_image() -> Image {
+    let (width, height) = (BUNNY[0].len() as u32, BUNNY.len() as u32);
+    let data = BUNNY
+        .iter()
+        .flat_map(|row| row.bytes())
+        .flat_map(|pixel| match pixel {
+            b'#' => [64, 64, 64, 255],
+            b'w' => [255, 255, 255, 255],
+            b'p' => [255, 170, 200, 255],
+            _ => [0, 0, 0, 0],
+        })
+        .collect();
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+fn spawn_bunnies(
+    In(num): In<u32>,
+    mut commands: Commands,
+    viewport: Res<Viewport>,
+    texture: Res<BunnyTexture>,
+    mut rng: ResMut<PseudoRng>,
+) {
+    let rng = &mut rng.0;
+    let top_left = Vec2::new(-viewport.width, viewport.height) / 2.;
+
+    for _ in 0..num {
+        commands.spawn((
+            Bunny {
+                velocity: Vec2::new(rng.gen_range(0.0..600.0), rng.gen_range(-300.0..300.0)),
+                jump: rng.gen_range(300.0..900.0),
+            },
+            Sprite {
+                image: texture.0.clone(),
+                custom_size: Some(BUNNY_SIZE),
+                ..default()
+            },
+            Transform::from_translation(top_left.extend(0.)),
+        ));
+    }
+}
+
+fn movement(
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    viewport: Res<Viewport>,
+    mut bunnies_query: Query<(&mut Bunny, &mut Transform)>,
+) {
+    let dt = timestep.delta_secs(&time);
+    let max = Vec2::new(viewport.width, viewport.height) / 2.;
+    let min = -max;
+    bunnies_query
+        .par_iter_mut()
+        .for_each(|(mut bunny, mut transform)| {
+            let position = &mut transform.translation;
+            position.x += bunny.velocity.x * dt;
+            position.y += bunny.velocity.y * dt;
+            bunny.velocity.y -= GRAVITY * dt;
+
+            if position.x > max.x {
+                bunny.velocity.x = -bunny.velocity.x;
+                position.x = max.x;
+            } else if position.x < min.x {
+                bunny.velocity.x = -bunny.velocity.x;
+                position.x = min.x;
+            }
+
+            if position.y < min.y {
+                bunny.velocity.y = (-bunny.velocity.y * BOUNCE).max(bunny.jump);
+                position.y = min.y;
+            } else if position.y > max.y {
+                bunny.velocity.y = 0.;
+                position.y = max.y;
+            }
+        });
+}
@@ -0,0 +1,469 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    hierarchy::{HierarchyShape, MAX_DEPTH, MAX_SPRITES},
+    rectangles::{FillColor, RectangleRender, ZOrder},
+    scenario::DEFAULT_SEED,
+};
+
+pub const USAGE: &str = "\
+Usage: bevy_vs_pixi [OPTIONS]
+
+Options:
+  --scenario <NAME>    Workload to run, switch with Tab [default: rectangles]
+  --count <N>          Number of objects to start with [default: 250]
+  --depth <N>          Nested sprites per object in the hierarchy scenario,
+                       from 1 to 16 [default: 4]
+  --fill <COLOR>       Rectangle fill: workaround (almost white), white or
+                       random, to measure batching with mixed colours
+                       [default: workaround]
+  --render <PATH>      How rectangles are drawn: sprites (a border sprite and a
+                       fill sprite), material (one quad) or mesh (Mesh2d
+                       quads with ColorMaterials), switch with R
+                       [default: sprites]
+  --z-order <ORDER>    Rectangle depths: random, constant, spawn (later in
+                       front) or bands:K (a random one of K layers), to
+                       measure depth sorting [default: random]
+  --churn <PCT>        Share of objects replaced every frame in the churn
+                       scenario [default: 1]
+  --fan-out <N>        Children of each sprite in the hierarchy scenario, up to
+                       4096 sprites per object with --depth [default: 1]
+  --seed <N>           Seed for the scenario RNG [default: 395992934456271]
+  --resolution <WxH>   Window (or headless viewport) size [default: 1000x765.25]
+  --duration <SECS>    Exit after running for this many seconds, or how long
+                       --bench records for [default for --bench: 10]
+  --frames <N>         Exit after this many frames
+  --output <PATH>      Write the run's results to PATH.json and PATH.csv
+  --baseline <PATH>    Compare the run against results in PATH.json and exit
+                       with an error if it regressed
+  --tolerance <PCT>    How much worse than --baseline p50/p95 frame times and
+                       the max sustainable count may get [default: 5]
+  --fixed-dt <SECS>    Advance the simulation by this much every frame instead
+                       of the real frame time, for reproducible runs
+  --checksum           Hash the world state every frame and report it on exit,
+                       for the rectangles, churn and labels scenarios
+  --overlaps           Count overlapping rectangle pairs every frame with a
+                       spatial grid, to measure a broad phase
+  --headless           Run the simulation without a window or renderer
+  --hidden             Hide the scenario's objects while still simulating
+                       them, toggle with H
+  --no-overlay         Hide the stats overlay
+  --bench              Record frame times at --count and report them on exit
+  --warmup <SECS>      Time to run before --bench starts recording [default: 2]
+  --search             Search for the highest count that holds --target-fps
+  --target-fps <FPS>   Frame rate the search must hold [default: 60]
+  --sustain <SECS>     How long each search step must hold the target [default: 5]
+  -h, --help           Print this help";
+
+/// Run configuration, shared by the native CLI and the web build's query string.
+#[derive(Resource, Clone, Debug)]
+pub struct Config {
+    pub scenario: String,
+    pub count: u32,
+    pub depth: u32,
+    pub fan_out: u32,
+    pub fill: FillColor,
+    pub render: RectangleRender,
+    pub z_order: ZOrder,
+    /// Share of objects replaced every frame, as a fraction.
+    pub churn: f32,
+    pub seed: u64,
+    pub width: f32,
+    pub height: f32,
+    pub duration: Option<Duration>,
+    pub frames: Option<u32>,
+    pub output: Option<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    /// Allowed relative regression against the baseline, as a fraction.
+    pub tolerance: f64,
+    pub fixed_dt: Option<Duration>,
+    pub checksum: bool,
+    pub overlaps: bool,
+    pub headless: bool,
+    pub hidden: bool,
+    pub overlay: bool,
+    pub bench: bool,
+    pub warmup: Duration,
+    pub search: bool,
+    pub target_fps: f64,
+    pub sustain: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            scenario: "rectangles".to_string(),
+            count: 250,
+            depth: 4,
+            fan_out: 1,
+            fill: FillColor::Workaround,
+            render: RectangleRender::Sprites,
+            z_order: ZOrder::Random,
+            churn: 0.01,
+            seed: DEFAULT_SEED,
+            width: 1000.,
+            height: 765.25,
+            duration: None,
+            frames: None,
+            output: None,
+            baseline: None,
+            tolerance: 0.05,
+            fixed_dt: None,
+            checksum: false,
+            overlaps: false,
+            headless: false,
+            hidden: false,
+            overlay: true,
+            bench: false,
+            warmup: Duration::from_secs(2),
+            search: false,
+            target_fps: 60.,
+            sustain: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Help,
+    Invalid(String),
+}
+
+impl Config {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_args() -> Result<Self, ConfigError> {
+        Self::parse_args(std::env::args().skip(1))
+    }
+
+    pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(ConfigError::Help),
+                "--headless" => config.headless = true,
+                "--hidden" => config.hidden = true,
+                "--no-overlay" => config.overlay = false,
+                "--checksum" => config.checksum = true,
+                "--overlaps" => config.overlaps = true,
+                "--bench" => config.bench = true,
+                "--search" => config.search = true,
+                flag => {
+                    let Some(value) = args.next() else {
+                        return Err(ConfigError::Invalid(format!("missing value for {flag}")));
+                    };
+                    config.set(flag.trim_start_matches("--"), &value)?;
+                }
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_url() -> Result<Self, ConfigError> {
+        let query = web_sys::window()
+            .and_then(|window| window.location().search().ok())
+            .unwrap_or_default();
+        Self::parse_query(&query)
+    }
+
+    /// Parses a URL query string such as `?count=8000&seed=1&overlay=0`.
+    ///
+    /// Keys are the long option names without dashes. Flags take `1`/`true` or
+    /// `0`/`false`, and a flag without a value is turned on. Keys and values
+    /// are percent-decoded, with `+` standing for a space.
+    #[cfg(any(target_arch = "wasm32", test))]
+    pub fn parse_query(query: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let pairs = query.trim_start_matches('?').split('&');
+        for pair in pairs.filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some((key, value)) => config.set(&decode(key)?, &decode(value)?)?,
+                None if pair == "help" => return Err(ConfigError::Help),
+                None => config.set(&decode(pair)?, "1")?,
+            }
+        }
+        if config.output.is_some() || config.baseline.is_some() {
+            return Err(ConfigError::Invalid(
+                "output and baseline need a file system, web runs log their results instead"
+                    .into(),
+            ));
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Whether the run's frame times are reported, by benchmarking,
+    /// searching, or exporting or checking the results of a timed run.
+    pub fn measured(&self) -> bool {
+        self.bench || self.search || self.output.is_some() || self.baseline.is_some()
+    }
+
+    /// Checks options that only make sense together.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let shape = HierarchyShape {
+            depth: self.depth,
+            fan_out: self.fan_out,
+        };
+        if shape.sprites() > MAX_SPRITES {
+            return Err(ConfigError::Invalid(format!(
+                "depth {} with fan-out {} makes {} sprites per object, more than {MAX_SPRITES}",
+                self.depth,
+                self.fan_out,
+                shape.sprites()
+            )));
+        }
+        // Each of these ends the run its own way, so only one may be given.
+        // The bench takes its recording time from the duration.
+        if self.search && (self.bench || self.duration.is_some() || self.frames.is_some()) {
+            return Err(ConfigError::Invalid(
+                "search can't be combined with bench, duration or frames".into(),
+            ));
+        }
+        if self.frames.is_some() && (self.bench || self.duration.is_some()) {
+            return Err(ConfigError::Invalid(
+                "frames can't be combined with bench or duration".into(),
+            ));
+        }
+        let ends = self.bench || self.search || self.duration.is_some() || self.frames.is_some();
+        if self.baseline.is_some() && !ends {
+            return Err(ConfigError::Invalid(
+                "baseline needs a run that ends: bench, search, duration or frames".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "scenario" => self.scenario = value.to_string(),
+            "count" => self.count = parse(key, value)?,
+            "depth" => {
+                self.depth = parse(key, value)?;
+                if !(1..=MAX_DEPTH).contains(&self.depth) {
+                    return Err(ConfigError::Invalid(format!(
+                        "depth must be between 1 and {MAX_DEPTH}"
+                    )));
+                }
+            }
+            "fill" => self.fill = parse(key, value)?,
+            "render" => self.render = parse(key, value)?,
+            "z-order" => self.z_order = parse(key, value)?,
+            "churn" => {
+                self.churn = parse::<f32>(key, value)? / 100.;
+                if !(0. ..=1.).contains(&self.churn) {
+                    return Err(ConfigError::Invalid(
+                        "churn must be between 0 and 100".into(),
+                    ));
+                }
+            }
+            "fan-out" => {
+                self.fan_out = parse(key, value)?;
+                if self.fan_out == 0 {
+                    return Err(ConfigError::Invalid("fan-out must be at least 1".into()));
+                }
+            }
+            "seed" => self.seed = parse(key, value)?,
+            "resolution" => {
+                let Some((width, height)) = value.split_once('x') else {
+                    return Err(ConfigError::Invalid(format!(
+                        "invalid resolution '{value}', expected WxH"
+                    )));
+                };
+                self.width = parse(key, width)?;
+                self.height = parse(key, height)?;
+                let positive = |size: f32| size.is_finite() && size > 0.;
+                if !(positive(self.width) && positive(self.height)) {
+                    return Err(ConfigError::Invalid(format!(
+                        "invalid resolution '{value}', expected positive sizes"
+                    )));
+                }
+            }
+            "duration" => self.duration = Some(parse_duration(key, value)?),
+            "frames" => self.frames = Some(parse(key, value)?),
+            "output" => self.output = Some(PathBuf::from(value)),
+            "baseline" => self.baseline = Some(PathBuf::from(value)),
+            "tolerance" => {
+                self.tolerance = parse::<f64>(key, value)? / 100.;
+                if !(self.tolerance.is_finite() && self.tolerance >= 0.) {
+                    return Err(ConfigError::Invalid(
+                        "tolerance must be a non-negative number".into(),
+                    ));
+                }
+            }
+            "fixed-dt" => self.fixed_dt = Some(parse_duration(key, value)?),
+            "warmup" => self.warmup = parse_duration(key, value)?,
+            "target-fps" => {
+                self.target_fps = parse(key, value)?;
+                if !(self.target_fps.is_finite() && self.target_fps > 0.) {
+                    return Err(ConfigError::Invalid(
+                        "target-fps must be a positive number".into(),
+                    ));
+                }
+            }
+            "sustain" => self.sustain = parse_duration(key, value)?,
+            "headless" => self.headless = parse_flag(key, value)?,
+            "hidden" => self.hidden = parse_flag(key, value)?,
+            "overlay" => self.overlay = parse_flag(key, value)?,
+            "checksum" => self.checksum = parse_flag(key, value)?,
+            "overlaps" => self.overlaps = parse_flag(key, value)?,
+            "bench" => self.bench = parse_flag(key, value)?,
+            "search" => self.search = parse_flag(key, value)?,
+            _ => return Err(ConfigError::Invalid(format!("unknown option '{key}'"))),
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a percent-encoded query string component.
+#[cfg(any(target_arch = "wasm32", test))]
+fn decode(component: &str) -> Result<String, ConfigError> {
+    let invalid = || ConfigError::Invalid(format!("invalid percent-encoding in '{component}'"));
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut rest = component.bytes();
+    while let Some(byte) = rest.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [rest.next(), rest.next()];
+                let [Some(high), Some(low)] = hex else {
+                    return Err(invalid());
+                };
+                let hex = std::str::from_utf8(&[high, low])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                hex.ok_or_else(invalid)?
+            }
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| ConfigError::Invalid(format!("invalid value '{value}' for {key}")))
+}
+
+fn parse_flag(key: &str, value: &str) -> Result<bool, ConfigError> {
+    match value {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(ConfigError::Invalid(format!(
+            "invalid value '{value}' for {key}, expected 0 or 1"
+        ))),
+    }
+}
+
+fn parse_duration(key: &str, value: &str) -> Result<Duration, ConfigError> {
+    Duration::try_from_secs_f64(parse(key, value)?)
+        .map_err(|_| ConfigError::Invalid(format!("invalid value '{value}' for {key}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Result<Config, ConfigError> {
+        Config::parse_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parses_args() {
+        let config = args("--count 8000 --headless --tolerance 10 --resolution 800x600").unwrap();
+        assert_eq!(config.count, 8000);
+        assert!(config.headless);
+        assert_eq!(config.tolerance, 0.1);
+        assert_eq!((config.width, config.height), (800., 600.));
+        assert!(config.overlay);
+    }
+
+    #[test]
+    fn rejects_bad_args() {
+        assert!(matches!(args("--count many"), Err(ConfigError::Invalid(_))));
+        assert!(matches!(args("--count"), Err(ConfigError::Invalid(_))));
+        assert!(matches!(args("--nope 1"), Err(ConfigError::Invalid(_))));
+        assert!(matches!(
+            args("--target-fps inf"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            args("--baseline run"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(args("--baseline run --bench").is_ok());
+        assert!(args("--bench --duration 5").is_ok());
+        for resolution in ["0x0", "-5x10", "NaNxNaN", "800xinf"] {
+            assert!(matches!(
+                args(&format!("--resolution {resolution}")),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+        for ends in [
+            "--bench --search",
+            "--search --duration 5",
+            "--search --frames 100",
+            "--bench --frames 100",
+            "--duration 5 --frames 100",
+        ] {
+            assert!(matches!(args(ends), Err(ConfigError::Invalid(_))), "{ends}");
+        }
+        assert!(matches!(
+            args("--depth 16 --fan-out 3"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(args("--depth 12 --fan-out 2").is_ok());
+        assert!(matches!(args("--help"), Err(ConfigError::Help)));
+    }
+
+    #[test]
+    fn parses_query() {
+        let config = Config::parse_query("?count=8000&seed=1&overlay=0&checksum").unwrap();
+        assert_eq!(config.count, 8000);
+        assert_eq!(config.seed, 1);
+        assert!(!config.overlay);
+        assert!(config.checksum);
+        assert_eq!(
+            Config::parse_query("").unwrap().count,
+            Config::default().count
+        );
+    }
+
+    #[test]
+    fn decodes_query() {
+        let config = Config::parse_query("scenario=my%20run+a%2Fb").unwrap();
+        assert_eq!(config.scenario, "my run a/b");
+        assert!(matches!(
+            Config::parse_query("scenario=%2"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            Config::parse_query("scenario=%zz"),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_query() {
+        assert!(matches!(
+            Config::parse_query("count=many"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            Config::parse_query("overlay=maybe"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            Config::parse_query("?help"),
+            Err(ConfigError::Help)
+        ));
+        assert!(matches!(
+            Config::parse_query("duration=30&output=run"),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+
+/// Number of most recent frames [`FrameTimes`] keeps.
+const WINDOW: usize = 1000;
+
+/// Upper bounds of the histogram buckets, in milliseconds. The last bucket
+/// holds everything above the final bound.
+pub const HISTOGRAM_BOUNDS_MS: [f64; 6] = [4., 8., 16.7, 33.3, 50., 100.];
+
+/// Keeps a rolling window of raw frame times.
+///
+/// Unlike [`FrameTimeDiagnosticsPlugin`](bevy::diagnostic::FrameTimeDiagnosticsPlugin)'s
+/// smoothed FPS, the raw samples keep stutter visible in the percentiles and
+/// histogram.
+pub struct FrameStatsPlugin;
+
+impl Plugin for FrameStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameTimes>();
+        app.add_systems(Last, record_frame_time.in_set(RecordFrame));
+    }
+}
+
+/// Systems in `Last` that record the frame's times, for systems that report
+/// them to run after.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RecordFrame;
+
+#[derive(Resource, Default)]
+pub struct FrameTimes {
+    samples: VecDeque<Duration>,
+    /// Frames recorded since startup.
+    recorded: u64,
+}
+
+impl FrameTimes {
+    pub fn summary(&self) -> FrameTimeSummary {
+        FrameTimeSummary::new(self.samples.iter().copied())
+    }
+
+    /// Summarizes the frames recorded since `recorded` was [`Self::recorded`],
+    /// or the whole window if that is longer ago.
+    pub fn summary_since(&self, recorded: u64) -> FrameTimeSummary {
+        let frames = (self.recorded - recorded).min(self.samples.len() as u64) as usize;
+        FrameTimeSummary::new(self.samples.iter().rev().take(frames).copied())
+    }
+
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+}
+
+/// Splits frame times by the value a setting had, such as the hierarchy depth,
+/// to show how each value performs.
+#[derive(Resource)]
+pub struct FrameTimesPer<T> {
+    /// The current value and when it was set.
+    current: Option<(T, u64)>,
+}
+
+impl<T> Default for FrameTimesPer<T> {
+    fn default() -> Self {
+        Self { current: None }
+    }
+}
+
+impl<T> FrameTimesPer<T> {
+    /// Starts measuring `value`, returning the previous value and a summary of
+    /// the frames recorded while it was set.
+    pub fn switch(&mut self, value: T, frame_times: &FrameTimes) -> Option<(T, FrameTimeSummary)> {
+        let previous = self.current.replace((value, frame_times.recorded()));
+        previous.map(|(value, since)| (value, frame_times.summary_since(since)))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FrameTimeSummary {
+    pub frames: usize,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub histogram: [u32; HISTOGRAM_BOUNDS_MS.len() + 1],
+}
+
+impl FrameTimeSummary {
+    pub fn new(samples: impl IntoIterator<Item = Duration>) -> Self {
+        let mut samples = samples.into_iter().collect::<Vec<_>>();
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+
+        let frames = samples.len();
+        let total = samples.iter().sum::<Duration>();
+        let percentile =
+            |p: f64| samples[((p * frames as f64).ceil() as usize).clamp(1, frames) - 1];
+        let mut histogram = [0; HISTOGRAM_BOUNDS_MS.len() + 1];
+        for sample in &samples {
+            let ms = sample.as_secs_f64() * 1000.;
+            let bucket = HISTOGRAM_BOUNDS_MS
+                .iter()
+                .position(|&bound| ms < bound)
+                .unwrap_or(HISTOGRAM_BOUNDS_MS.len());
+            histogram[bucket] += 1;
+        }
+
+        Self {
+            frames,
+            total,
+            min: samples[0],
+            max: samples[frames - 1],
+            mean: total / frames as u32,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            histogram,
+        }
+    }
+
+    pub fn fps(&self) -> f64 {
+        if self.total.is_zero() {
+            return 0.;
+        }
+        self.frames as f64 / self.total.as_secs_f64()
+    }
+
+    /// Formats the percentiles as `p50/p95/p99` in milliseconds.
+    pub fn percentiles(&self) -> String {
+        format!(
+            "{:.2}/{:.2}/{:.2}",
+            ms(self.p50),
+            ms(self.p95),
+            ms(self.p99)
+        )
+    }
+
+    /// Formats the percentiles and frame rate for a one-line report.
+    pub fn brief(&self) -> String {
+        format!(
+            "p50/p95/p99 {} ms, {:.2} FPS",
+            self.percentiles(),
+            self.fps()
+        )
+    }
+
+    /// Formats the histogram as the share of frames in each bucket.
+    pub fn histogram_shares(&self) -> String {
+        let mut shares = String::new();
+        for (i, &count) in self.histogram.iter().enumerate() {
+            if i > 0 {
+                shares.push(' ');
+            }
+            match HISTOGRAM_BOUNDS_MS.get(i) {
+                Some(bound) => write!(shares, "<{bound}:"),
+                None => write!(shares, ">={}:", HISTOGRAM_BOUNDS_MS[i - 1]),
+            }
+            .unwrap();
+            let share = 100. * f64::from(count) / self.frames.max(1) as f64;
+            write!(shares, "{share:.0}%").unwrap();
+        }
+        shares
+    }
+}
+
+impl fmt::Display for FrameTimeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Frames: {} over {:.2}s",
+            self.frames,
+            self.total.as_secs_f64()
+        )?;
+        writeln!(
+            f,
+            "Frame time (ms): min {:.2}, mean {:.2}, max {:.2}",
+            ms(self.min),
+            ms(self.mean),
+            ms(self.max),
+        )?;
+        writeln!(f, "p50/p95/p99 (ms): {}", self.percentiles())?;
+        writeln!(f, "Histogram (ms): {}", self.histogram_shares())?;
+        writeln!(f, "FPS: {:.2}", self.fps())
+    }
+}
+
+pub fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.
+}
+
+fn record_frame_time(time: Res<Time<Real>>, mut frame_times: ResMut<FrameTimes>) {
+    if frame_times.samples.len() == WINDOW {
+        frame_times.samples.pop_front();
+    }
+    frame_times.samples.push_back(time.delta());
+    frame_times.recorded += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_frame_times() {
+        // Shuffled, to check that the samples get sorted.
+        let samples = (1..=100)
+            .rev()
+            .map(|ms| Duration::from_millis(ms * 37 % 101));
+        let summary = FrameTimeSummary::new(samples);
+        assert_eq!(summary.frames, 100);
+        assert_eq!(summary.min, Duration::from_millis(1));
+        assert_eq!(summary.max, Duration::from_millis(100));
+        assert_eq!(summary.mean, Duration::from_micros(50_500));
+        assert_eq!(summary.p50, Duration::from_millis(50));
+        assert_eq!(summary.p95, Duration::from_millis(95));
+        assert_eq!(summary.p99, Duration::from_millis(99));
+        assert_eq!(summary.histogram, [3, 4, 9, 17, 16, 50, 1]);
+    }
+
+    #[test]
+    fn summarizes_no_frame_times() {
+        let summary = FrameTimeSummary::new([]);
+        assert_eq!(summary.frames, 0);
+        assert_eq!(summary.p50, Duration::ZERO);
+        assert_eq!(summary.histogram, [0; HISTOGRAM_BOUNDS_MS.len() + 1]);
+        assert_eq!(summary.fps(), 0.);
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::{prelude::*, render::view::VisibilitySystems};
+
+use crate::{
+    frame_stats::{FrameTimes, FrameTimesPer},
+    results,
+    scenario::{ScenarioObject, Scenarios, Stats},
+};
+
+/// Hides every scenario object while the simulation keeps running, to split
+/// the simulation's share of the frame time from extraction and rendering.
+///
+/// H toggles it at runtime. Each toggle prints the frame times measured in the
+/// previous state.
+pub struct HiddenPlugin;
+
+impl Plugin for HiddenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hidden>();
+        app.init_resource::<FrameTimesPer<bool>>();
+        app.add_systems(
+            Update,
+            (
+                toggle.run_if(pressed_h),
+                report.run_if(resource_changed::<Hidden>),
+            )
+                .chain(),
+        );
+        app.add_systems(
+            PostUpdate,
+            hide_objects.before(VisibilitySystems::VisibilityPropagate),
+        );
+    }
+}
+
+/// Whether scenario objects are hidden.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct Hidden(pub bool);
+
+fn pressed_h(keyboard_input: Res<ButtonInput<KeyCode>>) -> bool {
+    keyboard_input.just_released(KeyCode::KeyH)
+}
+
+fn toggle(mut hidden: ResMut<Hidden>) {
+    hidden.0 = !hidden.0;
+}
+
+fn report(
+    hidden: Res<Hidden>,
+    stats: Res<Stats>,
+    scenarios: Res<Scenarios>,
+    frame_times: Res<FrameTimes>,
+    mut per_state: ResMut<FrameTimesPer<bool>>,
+) {
+    if let Some((was_hidden, summary)) = per_state.switch(hidden.0, &frame_times) {
+        results::report(&format!(
+            "{} {}, count {}: {}",
+            if was_hidden { "Hidden" } else { "Visible" },
+            scenarios.active().name(),
+            stats.count,
+            summary.brief()
+        ));
+    }
+}
+
+type NewObjects<'w, 's> = Query<'w, 's, &'static mut Visibility, Added<ScenarioObject>>;
+
+/// Applies [`Hidden`] to every object when it changes, and to new objects.
+fn hide_objects(
+    hidden: Res<Hidden>,
+    mut objects: ParamSet<(Query<&mut Visibility, With<ScenarioObject>>, NewObjects)>,
+) {
+    let visibility = if hidden.0 {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    if hidden.is_changed() {
+        for mut object_visibility in &mut objects.p0() {
+            object_visibility.set_if_neq(visibility);
+        }
+    } else if hidden.0 {
+        for mut object_visibility in &mut objects.p1() {
+            object_visibility.set_if_neq(visibility);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    frame_stats::{FrameTimes, FrameTimesPer},
+    rectangles::{BORDER_COLOR, FILL_COLOR},
+    results::report,
+    scenario::{
+        self, PseudoRng, Scenario, ScenarioObject, ScenarioSet, ScenarioUpdate, Stats, Timestep,
+    },
+    viewport::Viewport,
+};
+
+pub const MAX_DEPTH: u32 = 16;
+/// Most sprites in one object's tree, so wide trees can't exhaust memory.
+pub const MAX_SPRITES: u64 = 4096;
+
+const COLORS: [Color; 2] = [BORDER_COLOR, FILL_COLOR];
+/// Size of each level relative to its parent.
+const SHRINK: f32 = 0.85;
+
+/// Nested sprites where only the root moves, to measure what transform
+/// propagation through deep hierarchies costs.
+///
+/// Up and Down change the depth at runtime. Each change prints the frame times
+/// measured at the previous depth, which shows how the cost scales.
+pub struct DeepHierarchy;
+
+impl Scenario for DeepHierarchy {
+    fn name(&self) -> &'static str {
+        "hierarchy"
+    }
+
+    fn description(&self) -> &'static str {
+        "Chains of nested sprites where only the root moves"
+    }
+
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HierarchyShape>();
+        app.init_resource::<FrameTimesPer<HierarchyShape>>();
+        app.add_systems(
+            ScenarioUpdate,
+            (
+                depth_input,
+                report_depth
+                    .pipe(respawn)
+                    .run_if(resource_changed::<HierarchyShape>),
+            )
+                .chain()
+                .in_set(ScenarioSet::Spawn)
+                .ambiguous_with(ScenarioSet::Spawn)
+                .run_if(scenario::active(self.name())),
+        );
+        app.add_systems(
+            Update,
+            movement
+                .in_set(ScenarioSet::Simulate)
+                .ambiguous_with(ScenarioSet::Simulate)
+                .run_if(scenario::active(self.name())),
+        );
+    }
+
+    fn setup(&self, world: &mut World) {
+        // The first shape is the one the objects are spawned with, so it
+        // doesn't need a respawn, and earlier runs of the scenario don't count
+        // towards its frame times.
+        world.insert_resource(FrameTimesPer::<HierarchyShape>::default());
+    }
+
+    fn spawn(&self, world: &mut World, num: u32) {
+        world
+            .run_system_cached_with(spawn_hierarchies, num)
+            .expect("spawning hierarchies failed");
+    }
+}
+
+/// Shape of each object's tree of sprites.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HierarchyShape {
+    /// Number of nested levels, including the root, from 1 to [`MAX_DEPTH`].
+    pub depth: u32,
+    /// Children of every sprite above the last level.
+    pub fan_out: u32,
+}
+
+impl HierarchyShape {
+    /// Sprites in each object's tree, saturating on overflow.
+    pub fn sprites(&self) -> u64 {
+        let mut level = 1_u64;
+        let mut total = 0_u64;
+        for _ in 0..self.depth {
+            total = total.saturating_add(level);
+            level = level.saturating_mul(self.fan_out.into());
+        }
+        total
+    }
+}
+
+impl Default for HierarchyShape {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            fan_out: 1,
+        }
+    }
+}
+
+#[derive(Component)]
+#[require(ScenarioObject)]
+struct HierarchyRoot {
+    velocity: f32,
+    width: f32,
+}
+
+fn spawn_hierarchies(
+    In(num): In<u32>,
+    mut commands: Commands,
+    viewport: Res<Viewport>,
+    shape: Res<HierarchyShape>,
+    mut rng: ResMut<PseudoRng>,
+) {
+    let rng = &mut rng.0;
+    let (width, height) = (viewport.width, viewport.height);
+
+    for _ in 0..num {
+        let size = rng.gen::<f32>().mul_add(40., 10.);
+        commands
+            .spawn((
+                HierarchyRoot {
+                    velocity: rng.gen_range(60.0..120.0),
+                    width: size,
+                },
+                Sprite {
+                    color: COLORS[0],
+                    custom_size: Some(Vec2::splat(size)),
+                    ..default()
+                },
+                Transform::from_xyz(
+                    (rng.gen::<f32>() - 0.5) * width,
+                    (rng.gen::<f32>() - 0.5) * height,
+                    rng.gen::<f32>(),
+                ),
+            ))
+            .with_children(|children| spawn_level(children, *shape, 1, size * SHRINK));
+    }
+}
+
+fn spawn_level(parent: &mut ChildBuilder, shape: HierarchyShape, level: u32, size: f32) {
+    if level >= shape.depth {
+        return;
+    }
+
+    for i in 0..shape.fan_out {
+        let offset = (i as f32 - (shape.fan_out - 1) as f32 / 2.) * size * 0.2;
+        parent
+            .spawn((
+                Sprite {
+                    color: COLORS[level as usize % COLORS.len()],
+                    custom_size: Some(Vec2::splat(size)),
+                    ..default()
+                },
+                Transform::from_xyz(offset, 0., f32::EPSILON),
+            ))
+            .with_children(|children| spawn_level(children, shape, level + 1, size * SHRINK));
+    }
+}
+
+fn depth_input(keyboard_input: Res<ButtonInput<KeyCode>>, mut shape: ResMut<HierarchyShape>) {
+    let deeper = HierarchyShape {
+        depth: shape.depth + 1,
+        ..*shape
+    };
+    if keyboard_input.just_released(KeyCode::ArrowUp)
+        && deeper.depth <= MAX_DEPTH
+        && deeper.sprites() <= MAX_SPRITES
+    {
+        *shape = deeper;
+    }
+    if keyboard_input.just_released(KeyCode::ArrowDown) && shape.depth > 1 {
+        shape.depth -= 1;
+    }
+}
+
+/// Reports the frame times at the previous shape, returning whether there was
+/// one to switch from.
+fn report_depth(
+    shape: Res<HierarchyShape>,
+    stats: Res<Stats>,
+    frame_times: Res<FrameTimes>,
+    mut per_shape: ResMut<FrameTimesPer<HierarchyShape>>,
+) -> bool {
+    let Some((previous, summary)) = per_shape.switch(*shape, &frame_times) else {
+        return false;
+    };
+    report(&format!(
+        "Depth {} x{}, count {}: {}",
+        previous.depth,
+        previous.fan_out,
+        stats.count,
+        summary.brief()
+    ));
+    true
+}
+
+/// Rebuilds every object with the current shape, if it changed.
+fn respawn(In(changed): In<bool>, world: &mut World) {
+    if !changed {
+        return;
+    }
+    let count = world
+        .query_filtered::<(), With<HierarchyRoot>>()
+        .iter(world)
+        .len() as u32;
+    DeepHierarchy.despawn(world, count);
+    DeepHierarchy.spawn(world, count);
+}
+
+fn movement(
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    viewport: Res<Viewport>,
+    mut roots_query: Query<(&HierarchyRoot, &mut Transform)>,
+) {
+    let dt = timestep.delta_secs(&time);
+    let teleport_target = -(viewport.width / 2.);
+    roots_query
+        .par_iter_mut()
+        .for_each(|(root, mut transform)| {
+            transform.translation.x -= root.velocity * dt;
+            if transform.translation.x < teleport_target - root.width {
+                transform.translation.x = -transform.translation.x;
+            }
+        });
+}
@@ -0,0 +1,96 @@
+use std::fmt::Write;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    rectangles::{RectangleObject, SpawnCounter},
+    scenario::{self, PseudoRng, Scenario, ScenarioSet},
+    viewport::Viewport,
+};
+
+const FONT_SIZE: f32 = 16.;
+/// Rough width of a label, used to wrap it once it is fully off screen.
+const LABEL_WIDTH: f32 = FONT_SIZE * 4.;
+
+/// Text labels whose numbers change every frame, moving like the rectangles.
+///
+/// Every change goes through text layout and glyph atlas updates.
+pub struct Labels;
+
+impl Scenario for Labels {
+    fn name(&self) -> &'static str {
+        "labels"
+    }
+
+    fn checksummed(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Moving Text2d labels with numbers changing every frame"
+    }
+
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnCounter>();
+        app.add_systems(
+            Update,
+            tick.in_set(ScenarioSet::Simulate)
+                .run_if(scenario::active(self.name())),
+        );
+    }
+
+    fn spawn(&self, world: &mut World, num: u32) {
+        world
+            .run_system_cached_with(spawn_labels, num)
+            .expect("spawning labels failed");
+    }
+}
+
+#[derive(Component)]
+struct Ticker(u32);
+
+fn spawn_labels(
+    In(num): In<u32>,
+    mut commands: Commands,
+    viewport: Res<Viewport>,
+    mut rng: ResMut<PseudoRng>,
+    mut spawn_counter: ResMut<SpawnCounter>,
+) {
+    let rng = &mut rng.0;
+    let (width, height) = (viewport.width, viewport.height);
+
+    for _ in 0..num {
+        let ticker = rng.gen_range(0..10_000);
+        commands.spawn((
+            RectangleObject::new(
+                &mut spawn_counter,
+                &viewport,
+                rng.gen_range(60.0..120.0),
+                LABEL_WIDTH,
+            ),
+            Ticker(ticker),
+            Text2d::new(ticker.to_string()),
+            TextFont {
+                font_size: FONT_SIZE,
+                ..default()
+            },
+            TextColor(Color::BLACK),
+            Transform::from_xyz(
+                (rng.gen::<f32>() - 0.5) * width,
+                (rng.gen::<f32>() - 0.5) * height,
+                rng.gen::<f32>(),
+            ),
+        ));
+    }
+}
+
+fn tick(mut labels_query: Query<(&mut Ticker, &mut Text2d)>) {
+    labels_query
+        .par_iter_mut()
+        .for_each(|(mut ticker, mut text)| {
+            ticker.0 = ticker.0.wrapping_add(1);
+            text.0.clear();
+            write!(text.0, "{}", ticker.0).unwrap();
+        });
+}
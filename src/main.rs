@@ -1,41 +1,241 @@
-use std::{fmt::Write, time::Duration};
+use std::{fmt::Write, process::ExitCode, time::Duration};
 
 use bevy::{
-    app::MainScheduleOrder,
-    diagnostic::{Diagnostic, DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    app::{MainScheduleOrder, ScheduleRunnerPlugin},
+    core::{update_frame_count, FrameCount},
+    diagnostic::{
+        Diagnostic, DiagnosticPath, DiagnosticsPlugin, DiagnosticsStore, FrameTimeDiagnosticsPlugin,
+    },
     ecs::schedule::{LogLevel, ScheduleBuildSettings},
+    input::InputPlugin,
     prelude::*,
-    time::common_conditions::on_timer,
-    window::{PrimaryWindow, WindowMode, WindowResolution},
+    time::common_conditions::{on_real_timer, on_timer},
+    window::{PresentMode, PrimaryWindow, WindowMode, WindowResolution},
 };
 
-use rectangles::Stats;
+use baseline::Baseline;
+use config::{Config, ConfigError};
+use frame_stats::{ms, FrameTimes, RecordFrame};
+use hidden::Hidden;
+use hierarchy::HierarchyShape;
+use overlaps::{Overlaps, OVERLAPS};
+use rectangles::{Checksum, ChurnRate};
+use render_stats::RenderStatsPlugin;
+use results::RunContext;
+use scenario::{PseudoRng, ScenarioAppExt, Scenarios, Stats, Timestep};
+use system_timings::{timed, SystemTimings, SystemTimingsPlugin};
+use viewport::Viewport;
 
+mod baseline;
+mod bench;
+mod bordered_rect;
+mod bunnymark;
+mod config;
+mod frame_stats;
+mod hidden;
+mod hierarchy;
+mod labels;
+mod overlaps;
 mod rectangles;
+mod render_stats;
+mod results;
+mod scenario;
+mod search;
+mod system_timings;
+mod ui_nodes;
+mod viewport;
+
+fn main() -> ExitCode {
+    #[cfg(not(target_arch = "wasm32"))]
+    let config = match Config::from_args() {
+        Ok(config) => config,
+        Err(ConfigError::Help) => {
+            println!("{}", config::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(ConfigError::Invalid(message)) => {
+            eprintln!("error: {message}\n\n{}", config::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    #[cfg(target_arch = "wasm32")]
+    let config = match Config::from_url() {
+        Ok(config) => config,
+        Err(error) => {
+            let message = match error {
+                ConfigError::Help => config::USAGE.to_string(),
+                ConfigError::Invalid(message) => format!("error: {message}\n\n{}", config::USAGE),
+            };
+            web_sys::console::error_1(&message.into());
+            return ExitCode::from(2);
+        }
+    };
 
-fn main() {
     let mut app = App::new();
 
-    app.add_plugins(DefaultPlugins.set(WindowPlugin {
-        primary_window: Some(Window {
-            title: "Rectangle canvas benchmark".to_string(),
-            resolution: WindowResolution::new(1000., 765.25),
+    if let Some(path) = &config.baseline {
+        match Baseline::load(path, config.tolerance) {
+            Ok(baseline) => {
+                app.insert_resource(baseline);
+            }
+            Err(message) => {
+                eprintln!("error: {message}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    if config.headless {
+        app.add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)),
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+            DiagnosticsPlugin,
+        ));
+        app.add_systems(Update, log_fps.run_if(on_timer(Duration::from_secs(1))));
+    } else {
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                title: "Rectangle canvas benchmark".to_string(),
+                resolution: WindowResolution::new(config.width, config.height),
+                // Measured runs need the uncapped frame rate, or vsync hides
+                // any difference below the display's refresh rate.
+                present_mode: if config.measured() {
+                    PresentMode::AutoNoVsync
+                } else {
+                    PresentMode::default()
+                },
+                ..default()
+            }),
             ..default()
-        }),
-        ..default()
-    }));
-    app.insert_resource(ClearColor(Color::WHITE));
-    app.add_plugins(rectangles::RectanglesPlugin);
-    app.add_systems(Startup, setup_cameras);
-    app.add_systems(Startup, setup_ui);
-    app.add_systems(Update, full_screen_toggle.run_if(pressed_f));
-    app.add_systems(Update, update_stats.run_if(resource_changed::<Stats>));
-    app.add_systems(Update, update_fps.run_if(on_timer(Duration::from_secs(1))));
-
-    app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }));
+        app.add_plugins(bordered_rect::BorderedRectPlugin);
+        app.insert_resource(ClearColor(Color::WHITE));
+        app.add_systems(Startup, setup_cameras);
+        app.add_systems(Update, full_screen_toggle.run_if(pressed_f));
+        if config.overlay {
+            app.add_systems(Startup, setup_ui);
+            // Chained since they all write to the same text.
+            app.add_systems(
+                Update,
+                (
+                    timed("update_stats", update_stats).run_if(resource_changed::<Stats>),
+                    update_scenario.run_if(resource_changed::<Scenarios>),
+                    (
+                        update_fps,
+                        update_frame_times,
+                        update_system_times,
+                        update_overlaps.run_if(resource_exists::<Overlaps>),
+                    )
+                        .chain()
+                        .run_if(on_timer(Duration::from_secs(1))),
+                )
+                    .chain(),
+            );
+        }
+    }
+    app.insert_resource(Viewport {
+        width: config.width,
+        height: config.height,
+    });
+    app.insert_resource(Stats {
+        count: config.count,
+    });
+    app.insert_resource(Hidden(config.hidden));
+    app.insert_resource(PseudoRng::from_seed(config.seed));
+    app.insert_resource(ChurnRate(config.churn));
+    app.insert_resource(config.fill);
+    app.insert_resource(config.render);
+    app.insert_resource(config.z_order);
+    app.insert_resource(HierarchyShape {
+        depth: config.depth,
+        fan_out: config.fan_out,
+    });
+    if let Some(dt) = config.fixed_dt {
+        app.insert_resource(Timestep::Fixed(dt));
+    }
+    if config.checksum {
+        app.init_resource::<Checksum>();
+    }
+    if config.overlaps {
+        app.init_resource::<Overlaps>();
+    }
+    app.add_plugins(viewport::ViewportPlugin);
+    app.add_plugins(scenario::ScenarioPlugin);
+    app.add_plugins(hidden::HiddenPlugin);
+    app.add_scenario(rectangles::Rectangles);
+    app.add_scenario(rectangles::Spinning);
+    app.add_scenario(rectangles::Churn);
+    app.add_scenario(bunnymark::Bunnymark);
+    app.add_scenario(hierarchy::DeepHierarchy);
+    app.add_scenario(labels::Labels);
+    app.add_scenario(ui_nodes::UiNodes);
+    if !app
+        .world_mut()
+        .resource_mut::<Scenarios>()
+        .select(&config.scenario)
+    {
+        let scenarios = app.world().resource::<Scenarios>();
+        eprintln!(
+            "error: unknown scenario '{}', expected one of:",
+            config.scenario
+        );
+        for scenario in scenarios.iter() {
+            eprintln!("  {:<12} {}", scenario.name(), scenario.description());
+        }
+        return ExitCode::from(2);
+    }
+    let active = app.world().resource::<Scenarios>().active();
+    if config.checksum && !active.checksummed() {
+        eprintln!(
+            "error: --checksum does not cover the {} scenario",
+            active.name()
+        );
+        return ExitCode::from(2);
+    }
+    if config.search {
+        app.add_plugins(search::SearchPlugin {
+            target_fps: config.target_fps,
+            sustain: config.sustain,
+        });
+    }
+    if config.bench {
+        app.add_plugins(bench::BenchPlugin {
+            warmup: config.warmup,
+            duration: config.duration.unwrap_or(Duration::from_secs(10)),
+        });
+    } else if let Some(duration) = config.duration {
+        app.add_systems(
+            Last,
+            finish_run
+                .after(RecordFrame)
+                // Virtual time falls behind when frames take longer than its
+                // maximum delta.
+                .run_if(on_real_timer(duration)),
+        );
+    } else if let Some(frames) = config.frames {
+        // In `Last`, like the bench, so the checksum covers the current frame.
+        app.add_systems(
+            Last,
+            finish_run
+                .after(RecordFrame)
+                .before(update_frame_count)
+                .run_if(move |frame_count: Res<FrameCount>| frame_count.0 + 1 >= frames),
+        );
+    }
+    app.insert_resource(config);
+
+    app.add_plugins((
+        FrameTimeDiagnosticsPlugin,
+        frame_stats::FrameStatsPlugin,
+        RenderStatsPlugin,
+        SystemTimingsPlugin,
+    ));
 
     if cfg!(debug_assertions) {
-        for schedule in MainScheduleOrder::default().labels {
+        let schedules = app.world().resource::<MainScheduleOrder>().labels.clone();
+        for schedule in schedules {
             app.edit_schedule(schedule, |schedule| {
                 schedule.set_build_settings(ScheduleBuildSettings {
                     ambiguity_detection: LogLevel::Warn,
@@ -45,14 +245,18 @@ fn main() {
         }
     }
 
-    app.run();
+    if app.run().is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
 #[derive(Component)]
 struct StatsText;
 
 fn setup_cameras(mut commands: Commands) {
-    commands.spawn(Camera2d::default());
+    commands.spawn(Camera2d);
 }
 
 fn setup_ui(mut commands: Commands) {
@@ -63,6 +267,13 @@ fn setup_ui(mut commands: Commands) {
         },
         TextColor(Srgba::hex("a96cff").unwrap().into()),
     );
+    let detail_style = (
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        text_style.1,
+    );
 
     commands
         .spawn((
@@ -73,15 +284,29 @@ fn setup_ui(mut commands: Commands) {
                 padding: UiRect::all(Val::Px(5.)),
                 ..default()
             },
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9).into()),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9)),
         ))
         .with_children(|parent| {
             parent
                 .spawn((Text::default(), StatsText))
-                .with_child((TextSpan::new("Count: "), text_style.clone()))
+                .with_child((TextSpan::new("Scenario: "), text_style.clone()))
+                .with_child((TextSpan::new(""), text_style.clone()))
+                .with_child((TextSpan::new("\nCount: "), text_style.clone()))
                 .with_child((TextSpan::new(""), text_style.clone()))
                 .with_child((TextSpan::new("\nFPS: "), text_style.clone()))
-                .with_child((TextSpan::new("0.00"), text_style));
+                .with_child((TextSpan::new("0.00"), text_style.clone()))
+                .with_child((TextSpan::new("\nRender: "), text_style.clone()))
+                .with_child((TextSpan::new(""), text_style.clone()))
+                .with_child((TextSpan::new("\nmin/mean/max: "), detail_style.clone()))
+                .with_child((TextSpan::new(""), detail_style.clone()))
+                .with_child((TextSpan::new("\np50/p95/p99: "), detail_style.clone()))
+                .with_child((TextSpan::new(""), detail_style.clone()))
+                .with_child((TextSpan::new("\n"), detail_style.clone()))
+                .with_child((TextSpan::new(""), detail_style.clone()))
+                .with_child((TextSpan::new("\nSystems (ms):"), detail_style.clone()))
+                .with_child((TextSpan::new(""), detail_style.clone()))
+                .with_child((TextSpan::new("\nOverlaps: "), detail_style.clone()))
+                .with_child((TextSpan::new("off"), detail_style));
         });
 }
 
@@ -105,10 +330,20 @@ fn update_stats(
     stats: Res<Stats>,
     query: Query<Entity, With<StatsText>>,
     mut writer: TextUiWriter,
+) {
+    let text = query.single();
+    writer.text(text, 4).clear();
+    write!(writer.text(text, 4), "{}", stats.count).unwrap();
+}
+
+fn update_scenario(
+    scenarios: Res<Scenarios>,
+    query: Query<Entity, With<StatsText>>,
+    mut writer: TextUiWriter,
 ) {
     let text = query.single();
     writer.text(text, 2).clear();
-    write!(writer.text(text, 2), "{}", stats.count).unwrap();
+    write!(writer.text(text, 2), "{}", scenarios.active().name()).unwrap();
 }
 
 fn update_fps(
@@ -121,7 +356,104 @@ fn update_fps(
         .and_then(Diagnostic::smoothed)
     {
         let text = query.single();
-        writer.text(text, 4).clear();
-        write!(writer.text(text, 4), "{fps:.2}").unwrap();
+        writer.text(text, 6).clear();
+        write!(writer.text(text, 6), "{fps:.2}").unwrap();
     }
+    let render_stat = |path: &DiagnosticPath| {
+        diagnostics
+            .get(path)
+            .and_then(Diagnostic::value)
+            .unwrap_or_default()
+    };
+    let text = query.single();
+    writer.text(text, 8).clear();
+    write!(
+        writer.text(text, 8),
+        "{} batches, {} draws, {} sprites",
+        render_stat(&RenderStatsPlugin::SPRITE_BATCHES),
+        render_stat(&RenderStatsPlugin::DRAW_CALLS),
+        render_stat(&RenderStatsPlugin::EXTRACTED_SPRITES),
+    )
+    .unwrap();
+}
+
+fn update_frame_times(
+    frame_times: Res<FrameTimes>,
+    query: Query<Entity, With<StatsText>>,
+    mut writer: TextUiWriter,
+) {
+    let summary = frame_times.summary();
+    let text = query.single();
+    writer.text(text, 10).clear();
+    write!(
+        writer.text(text, 10),
+        "{:.2}/{:.2}/{:.2} ms",
+        ms(summary.min),
+        ms(summary.mean),
+        ms(summary.max)
+    )
+    .unwrap();
+    writer.text(text, 12).clear();
+    write!(writer.text(text, 12), "{} ms", summary.percentiles()).unwrap();
+    writer.text(text, 14).clear();
+    write!(writer.text(text, 14), "{}", summary.histogram_shares()).unwrap();
+}
+
+fn update_system_times(
+    diagnostics: Res<DiagnosticsStore>,
+    timings: Res<SystemTimings>,
+    query: Query<Entity, With<StatsText>>,
+    mut writer: TextUiWriter,
+) {
+    let text = query.single();
+    let mut span = writer.text(text, 16);
+    span.clear();
+    for (name, path) in timings.paths() {
+        let average = diagnostics
+            .get(path)
+            .and_then(Diagnostic::average)
+            .unwrap_or_default();
+        write!(span, "\n  {name}: {average:.3}").unwrap();
+    }
+}
+
+fn update_overlaps(
+    diagnostics: Res<DiagnosticsStore>,
+    query: Query<Entity, With<StatsText>>,
+    mut writer: TextUiWriter,
+) {
+    if let Some(overlaps) = diagnostics.get(&OVERLAPS).and_then(Diagnostic::value) {
+        let text = query.single();
+        writer.text(text, 18).clear();
+        write!(writer.text(text, 18), "{overlaps}").unwrap();
+    }
+}
+
+fn log_fps(
+    diagnostics: Res<DiagnosticsStore>,
+    frame_times: Res<FrameTimes>,
+    stats: Res<Stats>,
+    scenarios: Res<Scenarios>,
+) {
+    if let Some(fps) = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(Diagnostic::smoothed)
+    {
+        let overlaps = diagnostics
+            .get(&OVERLAPS)
+            .and_then(Diagnostic::value)
+            .map(|overlaps| format!(" Overlaps: {overlaps}"))
+            .unwrap_or_default();
+        println!(
+            "Scenario: {} Count: {} FPS: {fps:.2} p50/p95/p99: {} ms{overlaps}",
+            scenarios.active().name(),
+            stats.count,
+            frame_times.summary().percentiles()
+        );
+    }
+}
+
+fn finish_run(frame_times: Res<FrameTimes>, stats: Res<Stats>, mut run: RunContext) {
+    let result = run.result("run", stats.count, frame_times.summary());
+    run.finish(&result);
 }
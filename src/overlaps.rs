@@ -0,0 +1,158 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{rectangles::RectangleObject, scenario::ScenarioSet, system_timings::timed};
+
+pub const OVERLAPS: DiagnosticPath = DiagnosticPath::const_new("rectangles/overlaps");
+
+/// Number of overlapping rectangle pairs in the latest frame.
+///
+/// Insert it to have overlaps detected after every frame's movement, as a
+/// broad phase would in a game. Rectangles are treated as unrotated squares of
+/// their spawn width.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct Overlaps(pub u32);
+
+/// Adds overlap detection for rectangles, running after every scenario's
+/// movement.
+pub(crate) fn build(app: &mut App) {
+    app.register_diagnostic(Diagnostic::new(OVERLAPS));
+    app.add_systems(
+        Update,
+        timed("overlaps", count_overlaps)
+            .after(ScenarioSet::Simulate)
+            .run_if(resource_exists::<Overlaps>),
+    );
+}
+
+/// Uniform grid of square cells, each listing the rectangles touching it.
+#[derive(Default)]
+struct SpatialGrid {
+    bounds: Vec<Rect>,
+    /// Cells are kept between frames to reuse their allocations.
+    cells: HashMap<IVec2, Vec<u32>>,
+}
+
+impl SpatialGrid {
+    fn count_overlaps(&mut self) -> u32 {
+        // Cells as large as the largest rectangle keep each rectangle within
+        // at most 2x2 cells.
+        let cell_size = self.bounds.iter().map(Rect::width).fold(1., f32::max);
+        let cell_of = |point: Vec2| (point / cell_size).floor().as_ivec2();
+
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        for (i, bounds) in self.bounds.iter().enumerate() {
+            let (min, max) = (cell_of(bounds.min), cell_of(bounds.max));
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    self.cells
+                        .entry(IVec2::new(x, y))
+                        .or_default()
+                        .push(i as u32);
+                }
+            }
+        }
+
+        let mut pairs = 0;
+        for (&cell, members) in &self.cells {
+            for (n, &a) in members.iter().enumerate() {
+                for &b in &members[n + 1..] {
+                    let overlap = self.bounds[a as usize].intersect(self.bounds[b as usize]);
+                    // Pairs sharing several cells only count in the one
+                    // holding the corner of their overlap.
+                    if !overlap.is_empty() && cell_of(overlap.min) == cell {
+                        pairs += 1;
+                    }
+                }
+            }
+        }
+        pairs
+    }
+}
+
+fn count_overlaps(
+    rectangles_query: Query<(&RectangleObject, &Transform)>,
+    mut grid: Local<SpatialGrid>,
+    mut overlaps: ResMut<Overlaps>,
+    mut diagnostics: Diagnostics,
+) {
+    grid.bounds.clear();
+    grid.bounds
+        .extend(rectangles_query.iter().map(|(r, transform)| {
+            Rect::from_center_size(transform.translation.truncate(), Vec2::splat(r.width()))
+        }));
+    overlaps.0 = grid.count_overlaps();
+    diagnostics.add_measurement(&OVERLAPS, || f64::from(overlaps.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    fn grid_count(bounds: &[Rect]) -> u32 {
+        SpatialGrid {
+            bounds: bounds.to_vec(),
+            ..default()
+        }
+        .count_overlaps()
+    }
+
+    fn brute_force_count(bounds: &[Rect]) -> u32 {
+        let mut pairs = 0;
+        for (n, a) in bounds.iter().enumerate() {
+            for b in &bounds[n + 1..] {
+                if !a.intersect(*b).is_empty() {
+                    pairs += 1;
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn counts_pairs_sharing_cells_once() {
+        // Far from the others, to make the cells 10 wide.
+        let sizer = Rect::new(100., 100., 110., 110.);
+        let cases = [
+            // Sharing one cell.
+            ((1., 1., 5., 5.), (2., 2., 6., 6.), 1),
+            // Sharing two cells.
+            ((8., 1., 12., 5.), (9., 2., 13., 6.), 1),
+            // Sharing four cells.
+            ((8., 8., 12., 12.), (9., 9., 13., 13.), 1),
+            // Touching edges.
+            ((0., 0., 5., 5.), (5., 0., 10., 5.), 0),
+            // Touching corners across four cells.
+            ((5., 5., 10., 10.), (10., 10., 15., 15.), 0),
+            // Negative coordinates.
+            ((-12., -12., -8., -8.), (-11., -11., -7., -7.), 1),
+        ];
+        for ((x0, y0, x1, y1), (u0, v0, u1, v1), expected) in cases {
+            let bounds = [Rect::new(x0, y0, x1, y1), Rect::new(u0, v0, u1, v1), sizer];
+            assert_eq!(brute_force_count(&bounds), expected, "{bounds:?}");
+            assert_eq!(grid_count(&bounds), expected, "{bounds:?}");
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let bounds = (0..500)
+            .map(|_| {
+                let center = Vec2::new(rng.gen_range(-200.0..200.), rng.gen_range(-200.0..200.));
+                Rect::from_center_size(center, Vec2::splat(rng.gen_range(5.0..40.)))
+            })
+            .collect::<Vec<_>>();
+        let expected = brute_force_count(&bounds);
+        assert!(expected > 0);
+        assert_eq!(grid_count(&bounds), expected);
+    }
+}
@@ -1,175 +1,614 @@
-use std::cmp::max;
+use std::{f32::consts::TAU, fmt, str::FromStr};
 
-use bevy::{
-    ecs::event::Events,
-    prelude::*,
-    window::{PrimaryWindow, WindowResized},
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
+
+use crate::{
+    bordered_rect::BorderedRectMaterial,
+    overlaps,
+    scenario::{
+        self, PseudoRng, Scenario, ScenarioObject, ScenarioSet, ScenarioUpdate, Scenarios,
+        Timestep,
+    },
+    system_timings::timed,
+    viewport::Viewport,
 };
-use rand::{Rng, SeedableRng};
-use rand_xoshiro::Xoshiro256PlusPlus;
 
-const BORDER_COLOR: Color = Color::BLACK;
+pub(crate) const BORDER_COLOR: Color = Color::BLACK;
 // Workaround for poor batching with mixed WHITE and other-colored sprites.
 // TODO https://github.com/bevyengine/bevy/issues/8100
-const FILL_COLOR: Color = Color::srgb(1.0 - f32::EPSILON, 1.0, 1.0);
+pub(crate) const FILL_COLOR: Color = Color::srgb(1.0 - f32::EPSILON, 1.0, 1.0);
+const BORDER_WIDTH: f32 = 1.5;
+/// How far spinning rectangles grow and shrink around their spawn size.
+const PULSE_AMPLITUDE: f32 = 0.25;
+
+/// Bordered rectangles moving left and wrapping around, as in the reference
+/// benchmark.
+pub struct Rectangles;
 
-pub struct RectanglesPlugin;
+impl Scenario for Rectangles {
+    fn name(&self) -> &'static str {
+        "rectangles"
+    }
+
+    fn checksummed(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Bordered rectangles moving left and wrapping around"
+    }
 
-impl Plugin for RectanglesPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Stats>();
-        app.init_resource::<PseudoRng>();
-        app.add_systems(Startup, setup);
+        init_rectangle_resources(app);
+        app.add_systems(
+            ScenarioUpdate,
+            render_switcher
+                .run_if(pressed_r.and(drawing_rectangles))
+                .in_set(ScenarioSet::Input),
+        );
         app.add_systems(
             Update,
-            (bounds_updater, movement, collision_detection).chain(),
+            (
+                timed(
+                    "bounds_updater",
+                    bounds_updater.run_if(resource_changed::<Viewport>),
+                ),
+                timed("movement", movement),
+                timed("collision_detection", collision_detection),
+            )
+                .chain()
+                .in_set(ScenarioSet::Simulate)
+                .run_if(any_with_component::<RectangleObject>),
+        );
+        overlaps::build(app);
+        app.add_systems(
+            PostUpdate,
+            update_checksum
+                .run_if(resource_exists::<Checksum>)
+                // After the UI layout, which also writes transforms.
+                .after(TransformSystem::TransformPropagate),
         );
-        app.add_systems(Update, mouse_handler);
+    }
+
+    fn spawn(&self, world: &mut World, num: u32) {
+        world
+            .run_system_cached_with(spawn_rectangles, num)
+            .expect("spawning rectangles failed");
     }
 }
 
-#[derive(Resource)]
-pub struct Stats {
-    pub count: u32,
+/// The reference rectangles, also rotating and pulsing in scale every frame.
+///
+/// Changing the whole transform of the bordered parent makes transform
+/// propagation to its fill child the bottleneck instead of pure translation.
+pub struct Spinning;
+
+impl Scenario for Spinning {
+    fn name(&self) -> &'static str {
+        "spinning"
+    }
+
+    fn description(&self) -> &'static str {
+        "Rectangles that also rotate and pulse in scale"
+    }
+
+    fn build(&self, app: &mut App) {
+        init_rectangle_resources(app);
+        app.add_systems(
+            Update,
+            spin.after(collision_detection)
+                .in_set(ScenarioSet::Simulate)
+                .run_if(scenario::active(self.name())),
+        );
+    }
+
+    fn spawn(&self, world: &mut World, num: u32) {
+        world
+            .run_system_cached_with(spawn_rectangles, num)
+            .expect("spawning rectangles failed");
+        world
+            .run_system_cached(add_spin)
+            .expect("spinning rectangles failed");
+    }
 }
 
-impl Default for Stats {
+/// The reference rectangles, with a share of them expiring and being replaced
+/// every frame.
+///
+/// Measures entity allocation, archetype moves and render extraction churn,
+/// which the steady-state scenarios never exercise.
+pub struct Churn;
+
+impl Scenario for Churn {
+    fn name(&self) -> &'static str {
+        "churn"
+    }
+
+    fn checksummed(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Rectangles with a share of them replaced every frame"
+    }
+
+    fn build(&self, app: &mut App) {
+        init_rectangle_resources(app);
+        app.init_resource::<ChurnRate>();
+        app.init_resource::<ChurnCarry>();
+        app.add_systems(
+            ScenarioUpdate,
+            churn
+                .in_set(ScenarioSet::Spawn)
+                .run_if(scenario::active(self.name())),
+        );
+    }
+
+    fn spawn(&self, world: &mut World, num: u32) {
+        world
+            .run_system_cached_with(spawn_rectangles, num)
+            .expect("spawning rectangles failed");
+    }
+}
+
+/// Share of rectangles replaced every frame in the [`Churn`] scenario.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ChurnRate(pub f32);
+
+impl Default for ChurnRate {
     fn default() -> Self {
-        Self { count: 250 }
+        Self(0.01)
+    }
+}
+
+/// Fraction of a rectangle left over from previous frames' churn, so that low
+/// counts still churn at the configured rate on average.
+#[derive(Resource, Default)]
+struct ChurnCarry(f32);
+
+/// How rectangles are drawn, switched with R at runtime.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RectangleRender {
+    /// A black sprite with a slightly smaller fill sprite as its child.
+    #[default]
+    Sprites,
+    /// A single quad drawing both with a [`BorderedRectMaterial`].
+    Material,
+    /// Like the sprites, but as `Mesh2d` quads with shared `ColorMaterial`s.
+    Mesh,
+}
+
+impl FromStr for RectangleRender {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sprites" => Ok(RectangleRender::Sprites),
+            "material" => Ok(RectangleRender::Material),
+            "mesh" => Ok(RectangleRender::Mesh),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for RectangleRender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RectangleRender::Sprites => "sprites",
+            RectangleRender::Material => "material",
+            RectangleRender::Mesh => "mesh",
+        })
     }
 }
 
+/// Number of times the render path was switched with R.
+#[derive(Resource, Default)]
+pub struct RenderSwitches(pub u32);
+
+/// Assets shared by every rectangle.
 #[derive(Resource)]
-pub struct PseudoRng(Xoshiro256PlusPlus);
+struct RectangleAssets {
+    /// Unit quad, scaled to each rectangle's size.
+    quad: Handle<Mesh>,
+    border: Handle<ColorMaterial>,
+}
 
-impl Default for PseudoRng {
-    fn default() -> Self {
-        Self(Xoshiro256PlusPlus::seed_from_u64(395992934456271))
+impl FromWorld for RectangleAssets {
+    fn from_world(world: &mut World) -> Self {
+        // Headless apps have no mesh assets, and their rectangles are never drawn.
+        let quad = world
+            .get_resource_mut::<Assets<Mesh>>()
+            .map(|mut meshes| meshes.add(Rectangle::new(1., 1.)))
+            .unwrap_or_default();
+        let border = world
+            .get_resource_mut::<Assets<ColorMaterial>>()
+            .map(|mut materials| materials.add(BORDER_COLOR))
+            .unwrap_or_default();
+        Self { quad, border }
+    }
+}
+
+/// Fill materials shared by every rectangle, unless fills are random.
+#[derive(Default)]
+struct SharedFills {
+    bordered: Option<Handle<BorderedRectMaterial>>,
+    color: Option<Handle<ColorMaterial>>,
+}
+
+/// Colour of the rectangles' fill.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillColor {
+    /// Almost white, which keeps batches from breaking between fills and the
+    /// black borders.
+    #[default]
+    Workaround,
+    /// Pure white, as in the reference benchmark.
+    White,
+    /// A random colour for every rectangle.
+    Random,
+}
+
+impl FillColor {
+    fn pick(self, rng: &mut impl Rng) -> Color {
+        match self {
+            FillColor::Workaround => FILL_COLOR,
+            FillColor::White => Color::WHITE,
+            FillColor::Random => Color::srgb(rng.gen(), rng.gen(), rng.gen()),
+        }
+    }
+}
+
+impl FromStr for FillColor {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "workaround" => Ok(FillColor::Workaround),
+            "white" => Ok(FillColor::White),
+            "random" => Ok(FillColor::Random),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for FillColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FillColor::Workaround => "workaround",
+            FillColor::White => "white",
+            FillColor::Random => "random",
+        })
+    }
+}
+
+/// How rectangles are spread over depth, which decides how sprites are sorted
+/// and batched.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ZOrder {
+    /// A random depth for every rectangle, as in the reference benchmark.
+    #[default]
+    Random,
+    /// The same depth for every rectangle.
+    Constant,
+    /// Later rectangles in front of earlier ones.
+    Spawn,
+    /// A random one of this many evenly spaced layers for every rectangle.
+    Bands(u32),
+}
+
+impl ZOrder {
+    /// Distinct depths [`ZOrder::Spawn`] cycles through. Their spacing of two
+    /// [`f32::EPSILON`] keeps every fill between its border and the next one.
+    const SPAWN_LAYERS: u64 = 1 << 22;
+
+    /// Picks a depth in `[0, 1)`.
+    fn pick(self, spawn_index: u64, rng: &mut impl Rng) -> f32 {
+        match self {
+            ZOrder::Random => rng.gen(),
+            ZOrder::Constant => 0.,
+            ZOrder::Spawn => (spawn_index % Self::SPAWN_LAYERS) as f32 / Self::SPAWN_LAYERS as f32,
+            ZOrder::Bands(bands) => rng.gen_range(0..bands) as f32 / bands as f32,
+        }
+    }
+}
+
+impl FromStr for ZOrder {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(ZOrder::Random),
+            "constant" => Ok(ZOrder::Constant),
+            "spawn" => Ok(ZOrder::Spawn),
+            _ => match s.strip_prefix("bands:").map(str::parse::<u32>) {
+                Some(Ok(bands)) if bands > 0 => Ok(ZOrder::Bands(bands)),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ZOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZOrder::Random => f.write_str("random"),
+            ZOrder::Constant => f.write_str("constant"),
+            ZOrder::Spawn => f.write_str("spawn"),
+            ZOrder::Bands(bands) => write!(f, "bands:{bands}"),
+        }
     }
 }
 
+/// Number of rectangles spawned so far, used to order them by spawn.
+#[derive(Resource, Default)]
+pub(crate) struct SpawnCounter(u64);
+
+/// Hash of every rectangle's state, in spawn order.
+///
+/// Insert it to have it recomputed after every frame's simulation. Two runs
+/// with the same seed, [`Timestep::Fixed`] step and frame count must end with
+/// the same checksum on every build and platform.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checksum(pub u64);
+
+/// Moves left and wraps around once past the left edge of the viewport.
+///
+/// Anything with it shares the rectangles' movement, wrapping and checksum.
 #[derive(Component)]
-struct RectangleObject {
+#[require(ScenarioObject)]
+pub(crate) struct RectangleObject {
+    spawn_index: u64,
     velocity: f32,
     width: f32,
     teleport_target: f32,
 }
 
-fn setup(
-    mut commands: Commands,
-    window: Query<&Window, With<PrimaryWindow>>,
-    stats: Res<Stats>,
-    mut rng: ResMut<PseudoRng>,
-) {
-    let Ok(window) = window.get_single() else {
-        return;
-    };
+impl RectangleObject {
+    pub(crate) fn new(
+        spawn_counter: &mut SpawnCounter,
+        viewport: &Viewport,
+        velocity: f32,
+        width: f32,
+    ) -> Self {
+        spawn_counter.0 += 1;
+        Self {
+            spawn_index: spawn_counter.0 - 1,
+            velocity,
+            width,
+            teleport_target: -(viewport.width / 2.) - width,
+        }
+    }
 
-    spawn_rectangles(&mut commands, window, &mut rng.0, stats.count);
+    pub(crate) fn width(&self) -> f32 {
+        self.width
+    }
 }
 
-fn mouse_handler(
-    mut commands: Commands,
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-    window: Query<&Window, With<PrimaryWindow>>,
-    mut stats: ResMut<Stats>,
-    rectangles: Query<Entity, With<RectangleObject>>,
-    mut rng: ResMut<PseudoRng>,
-) {
-    let Ok(window) = window.get_single() else {
-        return;
-    };
+#[derive(Component)]
+struct Spin {
+    /// Scale the rectangle was spawned with.
+    base_scale: Vec3,
+    /// Radians per second.
+    angular_velocity: f32,
+    /// Speed of the scale pulse, in radians per second.
+    pulse_speed: f32,
+    pulse_phase: f32,
+}
 
-    let old = stats.count;
-    if mouse_button_input.just_released(MouseButton::Left) {
-        stats.count = max(1, stats.count * 2);
-        spawn_rectangles(&mut commands, window, &mut rng.0, stats.count - old);
-    }
-    if mouse_button_input.just_released(MouseButton::Right) {
-        stats.count /= 2;
-        despawn_rectangles(&mut commands, rectangles, old - stats.count);
-    }
+fn init_rectangle_resources(app: &mut App) {
+    app.init_resource::<SpawnCounter>();
+    app.init_resource::<FillColor>();
+    app.init_resource::<RectangleRender>();
+    app.init_resource::<RenderSwitches>();
+    app.init_resource::<ZOrder>();
+    app.init_resource::<RectangleAssets>();
+}
+
+/// How new rectangles look, and the assets to draw them with.
+#[derive(SystemParam)]
+struct RectangleSpawner<'w> {
+    fill_color: Res<'w, FillColor>,
+    render: Res<'w, RectangleRender>,
+    z_order: Res<'w, ZOrder>,
+    assets: Res<'w, RectangleAssets>,
+    /// Missing without a renderer, like the color materials.
+    bordered_materials: Option<ResMut<'w, Assets<BorderedRectMaterial>>>,
+    color_materials: Option<ResMut<'w, Assets<ColorMaterial>>>,
 }
 
 fn spawn_rectangles(
-    commands: &mut Commands,
-    window: &Window,
-    rng: &mut Xoshiro256PlusPlus,
-    num: u32,
+    In(num): In<u32>,
+    mut commands: Commands,
+    viewport: Res<Viewport>,
+    mut rng: ResMut<PseudoRng>,
+    mut spawn_counter: ResMut<SpawnCounter>,
+    spawner: RectangleSpawner,
+    mut shared_fills: Local<SharedFills>,
 ) {
-    let (width, height) = (window.width(), window.height());
-    let teleport_target = -(width / 2.);
+    let RectangleSpawner {
+        fill_color,
+        render,
+        z_order,
+        assets,
+        mut bordered_materials,
+        mut color_materials,
+    } = spawner;
+    let rng = &mut rng.0;
+    let (width, height) = (viewport.width, viewport.height);
+    let mut add_bordered_material = |fill: Color| {
+        bordered_materials
+            .as_mut()
+            .map_or_else(Handle::default, |materials| {
+                materials.add(BorderedRectMaterial {
+                    border_color: BORDER_COLOR.into(),
+                    fill_color: fill.into(),
+                    border_width: BORDER_WIDTH,
+                })
+            })
+    };
+    let mut add_color_material = |fill: Color| {
+        color_materials
+            .as_mut()
+            .map_or_else(Handle::default, |materials| materials.add(fill))
+    };
 
     for _ in 0..num {
         let dimensions = Vec2::splat(rng.gen::<f32>().mul_add(40., 10.));
-        commands
-            .spawn((
-                RectangleObject {
-                    velocity: rng.gen_range(60.0..120.0),
-                    width: dimensions.x,
-                    teleport_target: teleport_target - dimensions.x,
-                },
-                Sprite {
-                    color: BORDER_COLOR,
-                    custom_size: Some(dimensions),
-                    ..default()
-                },
-                Transform::from_xyz(
-                    (rng.gen::<f32>() - 0.5) * width,
-                    (rng.gen::<f32>() - 0.5) * height,
-                    rng.gen::<f32>(),
-                ),
-            ))
-            .with_children(|children| {
-                children.spawn((
-                    Sprite {
-                        color: FILL_COLOR,
-                        custom_size: Some(dimensions - Vec2::splat(3.)),
-                        ..default()
-                    },
-                    Transform::from_xyz(0., 0., f32::EPSILON),
+        let object = RectangleObject::new(
+            &mut spawn_counter,
+            &viewport,
+            rng.gen_range(60.0..120.0),
+            dimensions.x,
+        );
+        let mut transform = Transform::from_xyz(
+            (rng.gen::<f32>() - 0.5) * width,
+            (rng.gen::<f32>() - 0.5) * height,
+            z_order.pick(object.spawn_index, rng),
+        );
+
+        match *render {
+            RectangleRender::Sprites => {
+                commands
+                    .spawn((
+                        object,
+                        Sprite {
+                            color: BORDER_COLOR,
+                            custom_size: Some(dimensions),
+                            ..default()
+                        },
+                        transform,
+                    ))
+                    .with_children(|children| {
+                        children.spawn((
+                            Sprite {
+                                color: fill_color.pick(rng),
+                                custom_size: Some(dimensions - Vec2::splat(2. * BORDER_WIDTH)),
+                                ..default()
+                            },
+                            Transform::from_xyz(0., 0., f32::EPSILON),
+                        ));
+                    });
+            }
+            RectangleRender::Material => {
+                // Random fills need a material each, the others share one.
+                let material = match *fill_color {
+                    FillColor::Random => add_bordered_material(fill_color.pick(rng)),
+                    fill => shared_fills
+                        .bordered
+                        .get_or_insert_with(|| add_bordered_material(fill.pick(rng)))
+                        .clone(),
+                };
+                transform.scale = dimensions.extend(1.);
+                commands.spawn((
+                    object,
+                    Mesh2d(assets.quad.clone()),
+                    MeshMaterial2d(material),
+                    transform,
                 ));
-            });
+            }
+            RectangleRender::Mesh => {
+                let fill = match *fill_color {
+                    FillColor::Random => add_color_material(fill_color.pick(rng)),
+                    fill => shared_fills
+                        .color
+                        .get_or_insert_with(|| add_color_material(fill.pick(rng)))
+                        .clone(),
+                };
+                transform.scale = dimensions.extend(1.);
+                commands
+                    .spawn((
+                        object,
+                        Mesh2d(assets.quad.clone()),
+                        MeshMaterial2d(assets.border.clone()),
+                        transform,
+                    ))
+                    .with_children(|children| {
+                        // The child inherits the parent's scale to the full size.
+                        let fill_scale = (dimensions - Vec2::splat(2. * BORDER_WIDTH)) / dimensions;
+                        children.spawn((
+                            Mesh2d(assets.quad.clone()),
+                            MeshMaterial2d(fill),
+                            Transform::from_xyz(0., 0., f32::EPSILON)
+                                .with_scale(fill_scale.extend(1.)),
+                        ));
+                    });
+            }
+        }
     }
 }
 
-fn despawn_rectangles(
-    commands: &mut Commands,
-    rectangles: Query<Entity, With<RectangleObject>>,
-    num: u32,
-) {
-    for r in rectangles.iter().take(num as usize) {
-        commands.entity(r).despawn_recursive();
+type NewRectangles<'w, 's> =
+    Query<'w, 's, (Entity, &'static Transform), (With<RectangleObject>, Without<Spin>)>;
+
+fn add_spin(mut commands: Commands, rectangles: NewRectangles, mut rng: ResMut<PseudoRng>) {
+    let rng = &mut rng.0;
+    for (r, transform) in &rectangles {
+        commands.entity(r).insert(Spin {
+            base_scale: transform.scale,
+            angular_velocity: rng.gen_range(-3.0..3.0),
+            pulse_speed: rng.gen_range(1.0..6.0),
+            pulse_phase: rng.gen_range(0.0..TAU),
+        });
     }
 }
 
-fn bounds_updater(
-    window: Query<Entity, With<PrimaryWindow>>,
-    resize_event: Res<Events<WindowResized>>,
-    mut rectangles_query: Query<&mut RectangleObject>,
+/// Replaces [`ChurnRate`] of the rectangles with new ones.
+fn churn(world: &mut World) {
+    let current = world
+        .query_filtered::<(), With<RectangleObject>>()
+        .iter(world)
+        .len();
+    let rate = world.resource::<ChurnRate>().0;
+    let mut carry = world.resource_mut::<ChurnCarry>();
+    carry.0 += current as f32 * rate;
+    let num = carry.0 as u32;
+    carry.0 -= num as f32;
+
+    Churn.despawn(world, num);
+    Churn.spawn(world, num);
+}
+
+fn pressed_r(keyboard_input: Res<ButtonInput<KeyCode>>) -> bool {
+    keyboard_input.just_released(KeyCode::KeyR)
+}
+
+/// Whether the active scenario draws its objects as [`RectangleRender`] says.
+fn drawing_rectangles(scenarios: Res<Scenarios>) -> bool {
+    [Rectangles.name(), Spinning.name(), Churn.name()].contains(&scenarios.active().name())
+}
+
+fn render_switcher(
+    mut render: ResMut<RectangleRender>,
+    mut switches: ResMut<RenderSwitches>,
+    mut scenarios: ResMut<Scenarios>,
 ) {
-    let Ok(window_id) = window.get_single() else {
-        return;
+    *render = match *render {
+        RectangleRender::Sprites => RectangleRender::Material,
+        RectangleRender::Material => RectangleRender::Mesh,
+        RectangleRender::Mesh => RectangleRender::Sprites,
     };
+    switches.0 += 1;
+    scenarios.reload();
+}
 
-    let mut reader = resize_event.get_cursor();
-    if let Some(e) = reader
-        .read(&resize_event)
-        .filter(|e| e.window == window_id)
-        .last()
-    {
-        let teleport_target = -(e.width / 2.);
-        rectangles_query.par_iter_mut().for_each(|mut r| {
-            r.teleport_target = teleport_target - r.width;
-        });
-    }
+fn bounds_updater(viewport: Res<Viewport>, mut rectangles_query: Query<&mut RectangleObject>) {
+    let teleport_target = -(viewport.width / 2.);
+    rectangles_query.par_iter_mut().for_each(|mut r| {
+        r.teleport_target = teleport_target - r.width;
+    });
 }
 
-fn movement(time: Res<Time>, mut rectangles_query: Query<(&RectangleObject, &mut Transform)>) {
+fn movement(
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    mut rectangles_query: Query<(&RectangleObject, &mut Transform)>,
+) {
+    let dt = timestep.delta_secs(&time);
     rectangles_query
         .par_iter_mut()
         .for_each(|(r, mut transform)| {
-            transform.translation.x -= r.velocity * time.delta_secs();
+            transform.translation.x -= r.velocity * dt;
         });
 }
 
@@ -182,3 +621,46 @@ fn collision_detection(mut rectangles_query: Query<(&RectangleObject, &mut Trans
             }
         });
 }
+
+fn spin(
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    mut rectangles_query: Query<(&mut Spin, &mut Transform)>,
+) {
+    let dt = timestep.delta_secs(&time);
+    rectangles_query
+        .par_iter_mut()
+        .for_each(|(mut spin, mut transform)| {
+            spin.pulse_phase = (spin.pulse_phase + spin.pulse_speed * dt) % TAU;
+            transform.rotate_z(spin.angular_velocity * dt);
+            transform.scale = spin.base_scale * spin.pulse_phase.sin().mul_add(PULSE_AMPLITUDE, 1.);
+        });
+}
+
+fn update_checksum(
+    mut checksum: ResMut<Checksum>,
+    rectangles_query: Query<(&RectangleObject, &Transform)>,
+) {
+    let mut rectangles = rectangles_query.iter().collect::<Vec<_>>();
+    rectangles.sort_unstable_by_key(|(r, _)| r.spawn_index);
+
+    // FNV-1a, which unlike std's hashers is stable across builds and platforms.
+    let mut hash = 0xcbf29ce484222325_u64;
+    let mut write = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3);
+        }
+    };
+    for (r, transform) in rectangles {
+        write(&r.spawn_index.to_le_bytes());
+        for value in [r.velocity, r.width, r.teleport_target]
+            .into_iter()
+            .chain(transform.translation.to_array())
+            .chain(transform.rotation.to_array())
+            .chain(transform.scale.to_array())
+        {
+            write(&value.to_bits().to_le_bytes());
+        }
+    }
+    checksum.0 = hash;
+}
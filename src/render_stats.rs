@@ -0,0 +1,153 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use bevy::{
+    core_pipeline::core_2d::{AlphaMask2d, Opaque2d, Transparent2d},
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+    render::{
+        render_phase::{
+            BinnedPhaseItem, PhaseItem, ViewBinnedRenderPhases, ViewSortedRenderPhases,
+        },
+        Render, RenderApp, RenderSet,
+    },
+    sprite::ExtractedSprites,
+};
+
+/// Measures what the renderer does with the sprites each frame.
+///
+/// Counts are taken in the render world and published as diagnostics in the
+/// main world, so they lag the simulation by up to a frame.
+pub struct RenderStatsPlugin;
+
+impl RenderStatsPlugin {
+    pub const SPRITE_BATCHES: DiagnosticPath = DiagnosticPath::const_new("render/sprite_batches");
+    /// Draw calls in the 2D phases: transparent, which holds the sprites, and
+    /// the opaque and alpha mask ones most meshes go to.
+    pub const DRAW_CALLS: DiagnosticPath = DiagnosticPath::const_new("render/draw_calls");
+    pub const EXTRACTED_SPRITES: DiagnosticPath =
+        DiagnosticPath::const_new("render/extracted_sprites");
+}
+
+impl Plugin for RenderStatsPlugin {
+    fn build(&self, app: &mut App) {
+        let counters = RenderCounters::default();
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.insert_resource(counters.clone());
+        render_app.add_systems(
+            Render,
+            (
+                count_sprite_batches,
+                count_draw_calls,
+                count_extracted_sprites,
+            )
+                .after(RenderSet::PrepareBindGroups)
+                .before(RenderSet::Render),
+        );
+
+        app.insert_resource(counters);
+        app.register_diagnostic(Diagnostic::new(Self::SPRITE_BATCHES));
+        app.register_diagnostic(Diagnostic::new(Self::DRAW_CALLS));
+        app.register_diagnostic(Diagnostic::new(Self::EXTRACTED_SPRITES));
+        app.add_systems(Update, publish);
+    }
+}
+
+/// Counters shared between the render and main worlds.
+#[derive(Resource, Clone, Default)]
+struct RenderCounters {
+    sprite_batches: Arc<AtomicU32>,
+    draw_calls: Arc<AtomicU32>,
+    extracted_sprites: Arc<AtomicU32>,
+}
+
+/// Counts the sprite items that start a batch, which the sprite renderer gives
+/// the batch's whole instance range.
+fn count_sprite_batches(
+    phases: Option<Res<ViewSortedRenderPhases<Transparent2d>>>,
+    extracted: Option<Res<ExtractedSprites>>,
+    counters: Res<RenderCounters>,
+) {
+    let mut num = 0;
+    if let Some(extracted) = extracted {
+        for phase in phases.iter().flat_map(|phases| phases.values()) {
+            num += phase
+                .items
+                .iter()
+                .filter(|item| {
+                    !item.batch_range().is_empty()
+                        && extracted
+                            .sprites
+                            .contains_key(&(item.entity(), item.main_entity()))
+                })
+                .count() as u32;
+        }
+    }
+    counters.sprite_batches.store(num, Ordering::Relaxed);
+}
+
+/// Counts draws the way the phases render them: one per batch of the sorted
+/// transparent phase, skipping the items merged into it, plus one per bin of
+/// the binned phases.
+fn count_draw_calls(
+    transparent_phases: Option<Res<ViewSortedRenderPhases<Transparent2d>>>,
+    opaque_phases: Option<Res<ViewBinnedRenderPhases<Opaque2d>>>,
+    alpha_mask_phases: Option<Res<ViewBinnedRenderPhases<AlphaMask2d>>>,
+    counters: Res<RenderCounters>,
+) {
+    let mut num = 0;
+    for phase in transparent_phases.iter().flat_map(|phases| phases.values()) {
+        let mut index = 0;
+        while index < phase.items.len() {
+            let batch_len = phase.items[index].batch_range().len();
+            if batch_len > 0 {
+                num += 1;
+            }
+            index += batch_len.max(1);
+        }
+    }
+    num += binned_draw_calls(opaque_phases.as_deref());
+    num += binned_draw_calls(alpha_mask_phases.as_deref());
+    counters.draw_calls.store(num, Ordering::Relaxed);
+}
+
+/// Each batchable bin is drawn at once where storage buffers are supported,
+/// which excludes WebGL 2, while unbatchable and non-mesh items draw alone.
+fn binned_draw_calls<BPI: BinnedPhaseItem>(phases: Option<&ViewBinnedRenderPhases<BPI>>) -> u32 {
+    let mut num = 0;
+    for phase in phases.iter().flat_map(|phases| phases.values()) {
+        num += phase.batchable_mesh_keys.len();
+        num += phase
+            .unbatchable_mesh_values
+            .values()
+            .map(|unbatchable| unbatchable.entities.len())
+            .sum::<usize>();
+        num += phase.non_mesh_items.len();
+    }
+    num as u32
+}
+
+fn count_extracted_sprites(
+    extracted: Option<Res<ExtractedSprites>>,
+    counters: Res<RenderCounters>,
+) {
+    let num = extracted.map_or(0, |extracted| extracted.sprites.len() as u32);
+    counters.extracted_sprites.store(num, Ordering::Relaxed);
+}
+
+fn publish(counters: Res<RenderCounters>, mut diagnostics: Diagnostics) {
+    for (path, counter) in [
+        (&RenderStatsPlugin::SPRITE_BATCHES, &counters.sprite_batches),
+        (&RenderStatsPlugin::DRAW_CALLS, &counters.draw_calls),
+        (
+            &RenderStatsPlugin::EXTRACTED_SPRITES,
+            &counters.extracted_sprites,
+        ),
+    ] {
+        diagnostics.add_measurement(path, || f64::from(counter.load(Ordering::Relaxed)));
+    }
+}
@@ -0,0 +1,304 @@
+use std::{fmt::Write, fs, io, path::Path, time::Duration};
+
+use bevy::{ecs::system::SystemParam, prelude::*, tasks::ComputeTaskPool, utils::SystemTime};
+
+use crate::{
+    baseline::Baseline,
+    config::Config,
+    frame_stats::{ms, FrameTimeSummary, HISTOGRAM_BOUNDS_MS},
+    rectangles::{Checksum, RectangleRender, RenderSwitches},
+    scenario::Scenarios,
+    system_timings::SystemTimings,
+};
+
+/// Fields describing what a run measured, which a baseline has to match for
+/// the runs to be comparable.
+pub const SETTINGS: [&str; 15] = [
+    "mode", "scenario", "count", "render", "fill", "z_order", "depth", "fan_out", "churn",
+    "fixed_dt", "overlaps", "hidden", "headless", "width", "height",
+];
+
+/// Everything worth keeping about a finished run.
+pub struct RunResult {
+    pub mode: &'static str,
+    pub scenario: &'static str,
+    pub count: u32,
+    /// Highest count that held the target frame rate, for searches.
+    pub max_count: Option<u32>,
+    pub target_fps: Option<f64>,
+    /// World state [`Checksum`](crate::rectangles::Checksum) at the end of the run.
+    pub checksum: Option<u64>,
+    /// How rectangles were drawn, or `mixed` if R switched it during the run.
+    pub render: String,
+    pub fill: String,
+    pub z_order: String,
+    pub depth: u32,
+    pub fan_out: u32,
+    /// Share of objects replaced every frame by the churn scenario, in percent.
+    pub churn: f32,
+    pub fixed_dt: Option<Duration>,
+    pub overlaps: bool,
+    /// Whether the scenario's objects started hidden.
+    pub hidden: bool,
+    pub headless: bool,
+    pub seed: u64,
+    pub width: f32,
+    pub height: f32,
+    pub frame_times: FrameTimeSummary,
+    /// Mean time per frame of each [`timed`](crate::system_timings::timed) span.
+    pub system_times: Vec<(&'static str, Duration)>,
+    pub profile: &'static str,
+    pub threads: usize,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+enum Value {
+    Int(u64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    fn to_json(&self) -> String {
+        match self {
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) if v.is_finite() => v.to_string(),
+            // JSON has no infinities or NaN.
+            Value::Float(_) => "null".into(),
+            Value::Str(v) => format!("\"{v}\""),
+            Value::Bool(v) => v.to_string(),
+            Value::Null => "null".into(),
+        }
+    }
+}
+
+impl RunResult {
+    pub fn new(
+        mode: &'static str,
+        scenario: &'static str,
+        config: &Config,
+        count: u32,
+        frame_times: FrameTimeSummary,
+    ) -> Self {
+        Self {
+            mode,
+            scenario,
+            count,
+            max_count: None,
+            target_fps: None,
+            checksum: None,
+            render: config.render.to_string(),
+            fill: config.fill.to_string(),
+            z_order: config.z_order.to_string(),
+            depth: config.depth,
+            fan_out: config.fan_out,
+            churn: config.churn * 100.,
+            fixed_dt: config.fixed_dt,
+            overlaps: config.overlaps,
+            hidden: config.hidden,
+            headless: config.headless,
+            seed: config.seed,
+            width: config.width,
+            height: config.height,
+            frame_times,
+            system_times: Vec::new(),
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            },
+            threads: ComputeTaskPool::get().thread_num(),
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |since_epoch| since_epoch.as_secs()),
+        }
+    }
+
+    fn fields(&self) -> Vec<(String, Value)> {
+        let frame_times = &self.frame_times;
+
+        let mut fields = vec![
+            ("mode".into(), Value::Str(self.mode.into())),
+            ("scenario".into(), Value::Str(self.scenario.into())),
+            ("count".into(), Value::Int(self.count.into())),
+            (
+                "max_count".into(),
+                self.max_count.map_or(Value::Null, |c| Value::Int(c.into())),
+            ),
+            (
+                "target_fps".into(),
+                self.target_fps.map_or(Value::Null, Value::Float),
+            ),
+            (
+                "checksum".into(),
+                self.checksum.map_or(Value::Null, Value::Int),
+            ),
+            ("render".into(), Value::Str(self.render.clone())),
+            ("fill".into(), Value::Str(self.fill.clone())),
+            ("z_order".into(), Value::Str(self.z_order.clone())),
+            ("depth".into(), Value::Int(self.depth.into())),
+            ("fan_out".into(), Value::Int(self.fan_out.into())),
+            ("churn".into(), Value::Float(self.churn.into())),
+            (
+                "fixed_dt".into(),
+                self.fixed_dt
+                    .map_or(Value::Null, |dt| Value::Float(dt.as_secs_f64())),
+            ),
+            ("overlaps".into(), Value::Bool(self.overlaps)),
+            ("hidden".into(), Value::Bool(self.hidden)),
+            ("headless".into(), Value::Bool(self.headless)),
+            ("seed".into(), Value::Int(self.seed)),
+            ("width".into(), Value::Float(self.width.into())),
+            ("height".into(), Value::Float(self.height.into())),
+            ("frames".into(), Value::Int(frame_times.frames as u64)),
+            ("fps".into(), Value::Float(frame_times.fps())),
+            ("min_ms".into(), Value::Float(ms(frame_times.min))),
+            ("mean_ms".into(), Value::Float(ms(frame_times.mean))),
+            ("max_ms".into(), Value::Float(ms(frame_times.max))),
+            ("p50_ms".into(), Value::Float(ms(frame_times.p50))),
+            ("p95_ms".into(), Value::Float(ms(frame_times.p95))),
+            ("p99_ms".into(), Value::Float(ms(frame_times.p99))),
+        ];
+        for (i, &count) in frame_times.histogram.iter().enumerate() {
+            let key = match HISTOGRAM_BOUNDS_MS.get(i) {
+                Some(bound) => format!("hist_lt_{bound}ms"),
+                None => format!("hist_ge_{}ms", HISTOGRAM_BOUNDS_MS[i - 1]),
+            };
+            fields.push((key, Value::Int(count.into())));
+        }
+        for &(name, time) in &self.system_times {
+            fields.push((format!("{name}_ms"), Value::Float(ms(time))));
+        }
+        fields.extend([
+            ("profile".into(), Value::Str(self.profile.into())),
+            ("threads".into(), Value::Int(self.threads as u64)),
+            ("timestamp".into(), Value::Int(self.timestamp)),
+        ]);
+        fields
+    }
+
+    /// The JSON text of each of the [`SETTINGS`], as [`Self::to_json`]
+    /// writes it.
+    pub fn settings(&self) -> Vec<(String, String)> {
+        self.fields()
+            .into_iter()
+            .filter(|(key, _)| SETTINGS.contains(&key.as_str()))
+            .map(|(key, value)| (key, value.to_json()))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n");
+        let fields = self.fields();
+        for (i, (key, value)) in fields.iter().enumerate() {
+            write!(json, "  \"{key}\": {}", value.to_json()).unwrap();
+            json.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+        }
+        json.push_str("}\n");
+        json
+    }
+
+    pub fn to_csv(&self) -> String {
+        let fields = self.fields();
+        let mut header = Vec::with_capacity(fields.len());
+        let mut row = Vec::with_capacity(fields.len());
+        for (key, value) in fields {
+            header.push(key);
+            row.push(match value {
+                Value::Int(v) => v.to_string(),
+                Value::Float(v) => v.to_string(),
+                Value::Str(v) => v,
+                Value::Bool(v) => v.to_string(),
+                Value::Null => String::new(),
+            });
+        }
+        format!("{}\n{}\n", header.join(","), row.join(","))
+    }
+
+    /// Writes the result next to `output` as `.json` and `.csv` files.
+    pub fn write(&self, output: &Path) -> io::Result<()> {
+        fs::write(output.with_extension("json"), self.to_json())?;
+        fs::write(output.with_extension("csv"), self.to_csv())
+    }
+}
+
+/// What every way of ending a run needs to build, export and check its
+/// [`RunResult`].
+#[derive(SystemParam)]
+pub struct RunContext<'w> {
+    config: Res<'w, Config>,
+    scenarios: Res<'w, Scenarios>,
+    timings: Res<'w, SystemTimings>,
+    checksum: Option<Res<'w, Checksum>>,
+    render: Res<'w, RectangleRender>,
+    render_switches: Res<'w, RenderSwitches>,
+    baseline: Option<Res<'w, Baseline>>,
+    exit: EventWriter<'w, AppExit>,
+}
+
+impl RunContext<'_> {
+    /// Builds the result of a run of the active scenario at `count`.
+    pub fn result(
+        &self,
+        mode: &'static str,
+        count: u32,
+        frame_times: FrameTimeSummary,
+    ) -> RunResult {
+        let mut result = RunResult::new(
+            mode,
+            self.scenarios.active().name(),
+            &self.config,
+            count,
+            frame_times,
+        );
+        result.checksum = self.checksum.as_ref().map(|checksum| checksum.0);
+        result.render = if self.render_switches.0 > 0 {
+            "mixed".into()
+        } else {
+            self.render.to_string()
+        };
+        result.system_times = self.timings.means();
+        result
+    }
+
+    /// Exports the result if an output path was configured, compares it
+    /// against the baseline if there is one and exits the app, with an error
+    /// if the check fails.
+    pub fn finish(&mut self, result: &RunResult) {
+        if let Some(checksum) = result.checksum {
+            report(&format!("Checksum: {checksum:016x}"));
+        }
+        // Web runs can't write files, so they log the results instead.
+        #[cfg(target_arch = "wasm32")]
+        report(&result.to_json());
+        if let Some(output) = &self.config.output {
+            if let Err(e) = result.write(output) {
+                eprintln!("failed to write {}: {e}", output.display());
+                self.exit.send(AppExit::error());
+                return;
+            }
+        }
+        if self
+            .baseline
+            .as_ref()
+            .is_some_and(|baseline| baseline.check(result))
+        {
+            eprintln!("Failed the baseline check");
+            self.exit.send(AppExit::error());
+            return;
+        }
+        self.exit.send(AppExit::Success);
+    }
+}
+
+/// Prints part of a run's report, to the browser console on the web, where
+/// standard output goes nowhere.
+pub fn report(text: &str) {
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::log_1(&text.into());
+    #[cfg(not(target_arch = "wasm32"))]
+    println!("{text}");
+}
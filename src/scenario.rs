@@ -0,0 +1,275 @@
+use std::{
+    cmp::{max, Ordering},
+    sync::Arc,
+    time::Duration,
+};
+
+use bevy::{app::MainScheduleOrder, ecs::schedule::ScheduleLabel, prelude::*};
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::system_timings::timed;
+
+pub const DEFAULT_SEED: u64 = 395992934456271;
+
+/// A workload to benchmark, such as the rectangles from the reference benchmark.
+///
+/// Scenarios only describe how to spawn their objects and simulate them. The
+/// [`ScenarioPlugin`] keeps [`Stats::count`] objects of the active scenario
+/// alive and tears them down when switching to another one.
+pub trait Scenario: Send + Sync + 'static {
+    /// Short identifier used to select the scenario, e.g. with `--scenario`.
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Whether [`Checksum`](crate::rectangles::Checksum) covers the scenario's
+    /// objects.
+    fn checksummed(&self) -> bool {
+        false
+    }
+
+    /// Adds the scenario's resources and per-frame systems. The systems should
+    /// only run while the scenario is [`active`], in the [`ScenarioSet`] for
+    /// what they do and that set's schedule.
+    fn build(&self, _app: &mut App) {}
+
+    /// Prepares the world before the scenario's first objects are spawned.
+    fn setup(&self, _world: &mut World) {}
+
+    /// Spawns `num` objects, each with a [`ScenarioObject`] on its root entity.
+    fn spawn(&self, world: &mut World, num: u32);
+
+    /// Despawns `num` of the scenario's objects.
+    fn despawn(&self, world: &mut World, num: u32) {
+        let objects = world
+            .query_filtered::<Entity, With<ScenarioObject>>()
+            .iter(world)
+            .take(num as usize)
+            .collect::<Vec<_>>();
+        for object in objects {
+            world.entity_mut(object).despawn_recursive();
+        }
+    }
+}
+
+/// Registers scenarios with the [`Scenarios`] registry.
+pub trait ScenarioAppExt {
+    fn add_scenario(&mut self, scenario: impl Scenario) -> &mut Self;
+}
+
+impl ScenarioAppExt for App {
+    fn add_scenario(&mut self, scenario: impl Scenario) -> &mut Self {
+        scenario.build(self);
+        self.init_resource::<Scenarios>();
+        self.world_mut()
+            .resource_mut::<Scenarios>()
+            .list
+            .push(Arc::new(scenario));
+        self
+    }
+}
+
+/// Runs the active scenario and the count handling shared by all scenarios.
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Stats>();
+        app.init_resource::<PseudoRng>();
+        app.init_resource::<Timestep>();
+        app.init_resource::<Scenarios>();
+        app.init_schedule(ScenarioUpdate);
+        app.world_mut()
+            .resource_mut::<MainScheduleOrder>()
+            .insert_after(PreUpdate, ScenarioUpdate);
+        app.configure_sets(
+            ScenarioUpdate,
+            (
+                ScenarioSet::Input,
+                ScenarioSet::Count,
+                ScenarioSet::Spawn,
+            )
+                .chain(),
+        );
+        app.add_systems(
+            ScenarioUpdate,
+            (
+                // Before the other input, so that it applies to the scenario
+                // being switched to.
+                scenario_switcher
+                    .run_if(pressed_tab)
+                    .before(ScenarioSet::Input),
+                timed("mouse_handler", mouse_handler).in_set(ScenarioSet::Input),
+                count_updater
+                    .run_if(resource_changed::<Stats>.or(resource_changed::<Scenarios>))
+                    .in_set(ScenarioSet::Count),
+            ),
+        );
+    }
+}
+
+/// Runs between [`PreUpdate`] and [`Update`] to change what objects exist.
+///
+/// Spawning and despawning need exclusive world access, which conflicts with
+/// every other system in a schedule. Running it here keeps those conflicts out
+/// of [`Update`], while objects spawned in a frame are still simulated in it.
+#[derive(ScheduleLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScenarioUpdate;
+
+/// What scenario systems do, in the order they run.
+///
+/// Systems of a scenario that shares no objects with the others only run while
+/// it is active, so they can be ambiguous with the rest of their set.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScenarioSet {
+    /// Input that changes the count or how objects are drawn, in
+    /// [`ScenarioUpdate`].
+    Input,
+    /// Keeping [`Stats::count`] objects of the active scenario alive, in
+    /// [`ScenarioUpdate`].
+    Count,
+    /// Scenario systems that spawn or despawn objects, in [`ScenarioUpdate`].
+    Spawn,
+    /// Scenario systems that move objects, in [`Update`].
+    Simulate,
+}
+
+/// All registered scenarios and which one is running.
+#[derive(Resource, Default)]
+pub struct Scenarios {
+    list: Vec<Arc<dyn Scenario>>,
+    active: usize,
+    /// The scenario whose objects currently exist.
+    loaded: Option<usize>,
+}
+
+impl Scenarios {
+    pub fn active(&self) -> &dyn Scenario {
+        &*self.list[self.active]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Scenario> {
+        self.list.iter().map(|scenario| &**scenario)
+    }
+
+    /// Respawns the active scenario's objects, e.g. after changing how they
+    /// are drawn.
+    pub fn reload(&mut self) {
+        self.loaded = None;
+    }
+
+    /// Switches to the scenario called `name`, returning whether it exists.
+    pub fn select(&mut self, name: &str) -> bool {
+        let Some(index) = self.list.iter().position(|s| s.name() == name) else {
+            return false;
+        };
+        self.active = index;
+        true
+    }
+}
+
+/// Run condition for systems that only apply to the scenario called `name`.
+pub fn active(name: &'static str) -> impl FnMut(Res<Scenarios>) -> bool + Clone {
+    move |scenarios: Res<Scenarios>| scenarios.active().name() == name
+}
+
+/// Marks the root entity of every object spawned by a scenario.
+#[derive(Component, Default)]
+pub struct ScenarioObject;
+
+#[derive(Resource)]
+pub struct Stats {
+    pub count: u32,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self { count: 250 }
+    }
+}
+
+#[derive(Resource)]
+pub struct PseudoRng(pub Xoshiro256PlusPlus);
+
+impl PseudoRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(Xoshiro256PlusPlus::seed_from_u64(seed))
+    }
+}
+
+impl Default for PseudoRng {
+    fn default() -> Self {
+        Self::from_seed(DEFAULT_SEED)
+    }
+}
+
+/// How far the simulation advances each frame.
+///
+/// With a fixed step, a given seed and frame count always produce the same
+/// world state, independent of the frame rate.
+#[derive(Resource, Clone, Copy, Default)]
+pub enum Timestep {
+    #[default]
+    Variable,
+    Fixed(Duration),
+}
+
+impl Timestep {
+    /// Seconds to advance the simulation by this frame.
+    pub fn delta_secs(&self, time: &Time) -> f32 {
+        match *self {
+            Timestep::Variable => time.delta_secs(),
+            Timestep::Fixed(dt) => dt.as_secs_f32(),
+        }
+    }
+}
+
+fn mouse_handler(mouse_button_input: Res<ButtonInput<MouseButton>>, mut stats: ResMut<Stats>) {
+    if mouse_button_input.just_released(MouseButton::Left) {
+        stats.count = max(1, stats.count * 2);
+    }
+    if mouse_button_input.just_released(MouseButton::Right) {
+        stats.count /= 2;
+    }
+}
+
+fn pressed_tab(keyboard_input: Res<ButtonInput<KeyCode>>) -> bool {
+    keyboard_input.just_released(KeyCode::Tab)
+}
+
+fn scenario_switcher(mut scenarios: ResMut<Scenarios>) {
+    scenarios.active = (scenarios.active + 1) % scenarios.list.len();
+}
+
+/// Loads the active scenario and spawns or despawns its objects until there
+/// are [`Stats::count`] of them.
+fn count_updater(world: &mut World) {
+    let scenarios = world.resource::<Scenarios>();
+    let (active, loaded) = (scenarios.active, scenarios.loaded);
+    let scenario = scenarios.list[active].clone();
+    if loaded != Some(active) {
+        let current = world
+            .query_filtered::<(), With<ScenarioObject>>()
+            .iter(world)
+            .len() as u32;
+        let previous = loaded.map_or_else(
+            || scenario.clone(),
+            |loaded| world.resource::<Scenarios>().list[loaded].clone(),
+        );
+        previous.despawn(world, current);
+        scenario.setup(world);
+        world.resource_mut::<Scenarios>().loaded = Some(active);
+    }
+
+    let current = world
+        .query_filtered::<(), With<ScenarioObject>>()
+        .iter(world)
+        .len() as u32;
+    let count = world.resource::<Stats>().count;
+    match count.cmp(&current) {
+        Ordering::Greater => scenario.spawn(world, count - current),
+        Ordering::Less => scenario.despawn(world, current - count),
+        Ordering::Equal => {}
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    frame_stats::FrameTimes,
+    results::{report, RunContext},
+    scenario::{ScenarioSet, ScenarioUpdate, Stats},
+};
+
+/// Time to let the frame rate settle after changing the count before measuring.
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+/// Finds the highest rectangle count that holds a target frame rate.
+///
+/// Starting from the configured count, the count is doubled until a step
+/// misses the target, then binary searched between the last good and first bad
+/// counts. Each step measures the mean frame rate over `sustain`.
+pub struct SearchPlugin {
+    pub target_fps: f64,
+    pub sustain: Duration,
+}
+
+impl Plugin for SearchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Search {
+            target_fps: self.target_fps,
+            sustain: self.sustain,
+            good: 0,
+            bad: None,
+            elapsed: Duration::ZERO,
+            frames: 0,
+        });
+        app.add_systems(
+            ScenarioUpdate,
+            search
+                .after(ScenarioSet::Input)
+                .before(ScenarioSet::Count),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct Search {
+    target_fps: f64,
+    sustain: Duration,
+    /// Highest count known to hold the target.
+    good: u32,
+    /// Lowest count known to miss the target.
+    bad: Option<u32>,
+    /// Time spent at the current count, including settling.
+    elapsed: Duration,
+    /// Frames measured at the current count, after settling.
+    frames: u32,
+}
+
+impl Search {
+    fn next_count(&self) -> Option<u32> {
+        match self.bad {
+            None => Some(self.good.saturating_mul(2)),
+            Some(bad) if bad - self.good > (self.good / 100).max(1) => {
+                Some(self.good + (bad - self.good) / 2)
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+fn search(
+    time: Res<Time<Real>>,
+    mut search: ResMut<Search>,
+    frame_times: Res<FrameTimes>,
+    mut stats: ResMut<Stats>,
+    mut run: RunContext,
+) {
+    search.elapsed += time.delta();
+    if search.elapsed <= SETTLE_TIME {
+        return;
+    }
+    search.frames += 1;
+    if search.elapsed < SETTLE_TIME + search.sustain {
+        return;
+    }
+
+    let fps = f64::from(search.frames) / (search.elapsed - SETTLE_TIME).as_secs_f64();
+    let held = fps >= search.target_fps;
+    report(&format!(
+        "Search step: count {} {} the target at {fps:.2} FPS",
+        stats.count,
+        if held { "held" } else { "missed" }
+    ));
+    if held {
+        search.good = stats.count;
+    } else {
+        search.bad = Some(stats.count);
+    }
+    search.elapsed = Duration::ZERO;
+    search.frames = 0;
+
+    if let Some(count) = search.next_count() {
+        stats.count = count.max(1);
+        return;
+    }
+
+    report(&format!(
+        "Max sustainable count: {} at {:.2} FPS",
+        search.good, search.target_fps
+    ));
+    let mut result = run.result("search", stats.count, frame_times.summary());
+    result.max_count = Some(search.good);
+    result.target_fps = Some(search.target_fps);
+    run.finish(&result);
+}
@@ -0,0 +1,160 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticMeasurement, DiagnosticPath, DiagnosticsStore},
+    ecs::{event::EventUpdates, schedule::SystemConfigs},
+    prelude::*,
+    render::RenderApp,
+    utils::Instant,
+};
+
+use crate::frame_stats::{ms, RecordFrame};
+
+/// Name of the span covering render world extraction.
+const EXTRACT: &str = "extract";
+
+/// Measures how long selected systems take each frame.
+///
+/// Systems opt in by being wrapped with [`timed`]. Each span is published as a
+/// `system/<name>` diagnostic in milliseconds, and [`SystemTimings`] keeps the
+/// mean since startup for the exported results.
+pub struct SystemTimingsPlugin;
+
+impl Plugin for SystemTimingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SystemTimings>();
+        app.add_systems(First, reset.after(EventUpdates));
+        app.add_systems(Last, publish.in_set(RecordFrame));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        let Some(extract) = render_app.take_extract() else {
+            return;
+        };
+        let extract_nanos = ExtractTime::default();
+        let nanos = extract_nanos.0.clone();
+        render_app.set_extract(move |main_world, render_world| {
+            let start = Instant::now();
+            extract(main_world, render_world);
+            nanos.store(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        });
+        app.insert_resource(extract_nanos);
+    }
+}
+
+/// Wraps `systems` in markers that time them as the span called `name`.
+///
+/// The span is wall time from before the first system starts to after the last
+/// one ends, so it includes scheduling overhead. Run conditions added to the
+/// result skip the markers too, leaving the span at zero for that frame.
+pub fn timed<M>(name: &'static str, systems: impl IntoSystemConfigs<M>) -> SystemConfigs {
+    // Markers of different spans only share the timings, so their order
+    // relative to each other does not matter.
+    (
+        (move |mut timings: ResMut<SystemTimings>| timings.start(name)).ambiguous_with_all(),
+        systems,
+        (move |mut timings: ResMut<SystemTimings>| timings.stop(name)).ambiguous_with_all(),
+    )
+        .chain()
+}
+
+#[derive(Resource, Default)]
+pub struct SystemTimings {
+    spans: Vec<Span>,
+}
+
+struct Span {
+    name: &'static str,
+    path: DiagnosticPath,
+    started: Option<Instant>,
+    frame: Duration,
+    total: Duration,
+    frames: u32,
+}
+
+impl SystemTimings {
+    /// Names and diagnostic paths of all spans, in the order they first ran.
+    pub fn paths(&self) -> impl Iterator<Item = (&'static str, &DiagnosticPath)> {
+        self.spans.iter().map(|span| (span.name, &span.path))
+    }
+
+    /// Mean time per frame of each span since startup.
+    pub fn means(&self) -> Vec<(&'static str, Duration)> {
+        self.spans
+            .iter()
+            .map(|span| (span.name, span.total / span.frames.max(1)))
+            .collect()
+    }
+
+    fn span(&mut self, name: &'static str) -> &mut Span {
+        let index = match self.spans.iter().position(|span| span.name == name) {
+            Some(index) => index,
+            None => {
+                self.spans.push(Span {
+                    name,
+                    path: DiagnosticPath::new(format!("system/{name}")),
+                    started: None,
+                    frame: Duration::ZERO,
+                    total: Duration::ZERO,
+                    frames: 0,
+                });
+                self.spans.len() - 1
+            }
+        };
+        &mut self.spans[index]
+    }
+
+    fn start(&mut self, name: &'static str) {
+        self.span(name).started = Some(Instant::now());
+    }
+
+    fn stop(&mut self, name: &'static str) {
+        let span = self.span(name);
+        if let Some(started) = span.started.take() {
+            span.frame += started.elapsed();
+        }
+    }
+}
+
+/// Duration of the latest extraction in nanoseconds, written by the render app.
+#[derive(Resource, Clone, Default)]
+struct ExtractTime(Arc<AtomicU64>);
+
+fn reset(mut timings: ResMut<SystemTimings>) {
+    for span in &mut timings.spans {
+        span.frame = Duration::ZERO;
+    }
+}
+
+fn publish(
+    mut timings: ResMut<SystemTimings>,
+    extract_time: Option<Res<ExtractTime>>,
+    mut store: ResMut<DiagnosticsStore>,
+) {
+    if let Some(extract_time) = extract_time {
+        // Extraction runs after the main app, so this is the previous frame's.
+        timings.span(EXTRACT).frame = Duration::from_nanos(extract_time.0.load(Ordering::Relaxed));
+    }
+
+    let time = Instant::now();
+    for span in &mut timings.spans {
+        span.total += span.frame;
+        span.frames += 1;
+        if store.get(&span.path).is_none() {
+            store.add(Diagnostic::new(span.path.clone()).with_suffix(" ms"));
+        }
+        if let Some(diagnostic) = store.get_mut(&span.path) {
+            diagnostic.add_measurement(DiagnosticMeasurement {
+                time,
+                value: ms(span.frame),
+            });
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    rectangles::{BORDER_COLOR, FILL_COLOR},
+    scenario::{self, PseudoRng, Scenario, ScenarioObject, ScenarioSet, Timestep},
+    viewport::Viewport,
+};
+
+/// Absolutely positioned bordered UI nodes bouncing around the viewport, to
+/// compare UI layout throughput against sprites.
+pub struct UiNodes;
+
+impl Scenario for UiNodes {
+    fn name(&self) -> &'static str {
+        "ui"
+    }
+
+    fn description(&self) -> &'static str {
+        "Absolutely positioned bordered UI nodes bouncing around"
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            movement
+                .in_set(ScenarioSet::Simulate)
+                .run_if(scenario::active(self.name())),
+        );
+    }
+
+    fn spawn(&self, world: &mut World, num: u32) {
+        world
+            .run_system_cached_with(spawn_nodes, num)
+            .expect("spawning UI nodes failed");
+    }
+}
+
+#[derive(Component)]
+#[require(ScenarioObject)]
+struct UiObject {
+    /// Top left corner, in logical pixels from the top left of the viewport.
+    position: Vec2,
+    velocity: Vec2,
+    size: f32,
+}
+
+fn spawn_nodes(
+    In(num): In<u32>,
+    mut commands: Commands,
+    viewport: Res<Viewport>,
+    mut rng: ResMut<PseudoRng>,
+) {
+    let rng = &mut rng.0;
+
+    for _ in 0..num {
+        let size = rng.gen::<f32>().mul_add(40., 10.);
+        let position = Vec2::new(
+            rng.gen::<f32>() * (viewport.width - size),
+            rng.gen::<f32>() * (viewport.height - size),
+        );
+        let velocity = Vec2::new(rng.gen_range(-120.0..120.0), rng.gen_range(-120.0..120.0));
+        commands.spawn((
+            UiObject {
+                position,
+                velocity,
+                size,
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(position.x),
+                top: Val::Px(position.y),
+                width: Val::Px(size),
+                height: Val::Px(size),
+                border: UiRect::all(Val::Px(1.5)),
+                ..default()
+            },
+            BackgroundColor(FILL_COLOR),
+            BorderColor(BORDER_COLOR),
+            // Keep the stats overlay on top.
+            ZIndex(-1),
+        ));
+    }
+}
+
+fn movement(
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    viewport: Res<Viewport>,
+    mut nodes_query: Query<(&mut UiObject, &mut Node)>,
+) {
+    let dt = timestep.delta_secs(&time);
+    let bounds = Vec2::new(viewport.width, viewport.height);
+    nodes_query
+        .par_iter_mut()
+        .for_each(|(mut object, mut node)| {
+            let object = &mut *object;
+            object.position += object.velocity * dt;
+            let max = bounds - object.size;
+            for axis in 0..2 {
+                if object.position[axis] < 0. || object.position[axis] > max[axis] {
+                    object.velocity[axis] = -object.velocity[axis];
+                    object.position[axis] = object.position[axis].clamp(0., max[axis].max(0.));
+                }
+            }
+            node.left = Val::Px(object.position.x);
+            node.top = Val::Px(object.position.y);
+        });
+}
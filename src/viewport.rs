@@ -0,0 +1,63 @@
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+
+pub struct ViewportPlugin;
+
+impl Plugin for ViewportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Viewport>();
+        if app.is_plugin_added::<WindowPlugin>() {
+            app.add_systems(PreStartup, init_from_window);
+            app.add_systems(PreUpdate, window_resized);
+        }
+    }
+}
+
+/// The area rectangles live in, in logical pixels.
+///
+/// Follows the primary window when there is one. Headless apps insert it
+/// directly to pick the simulated size.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct Viewport {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            width: 1000.,
+            height: 765.25,
+        }
+    }
+}
+
+fn init_from_window(mut viewport: ResMut<Viewport>, window: Query<&Window, With<PrimaryWindow>>) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+
+    viewport.width = window.width();
+    viewport.height = window.height();
+}
+
+fn window_resized(
+    mut viewport: ResMut<Viewport>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut resize_events: EventReader<WindowResized>,
+) {
+    let Ok(window_id) = window.get_single() else {
+        return;
+    };
+
+    if let Some(e) = resize_events
+        .read()
+        .filter(|e| e.window == window_id)
+        .last()
+    {
+        viewport.width = e.width;
+        viewport.height = e.height;
+    }
+}